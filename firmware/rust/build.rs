@@ -0,0 +1,316 @@
+//! Compiles the track layout into the node tables included by src/location.rs.
+//! The layout defaults to layout/track.txt, set LIGHT_RAIL_LAYOUT to build with another layout file.
+
+use std::{env, fmt::Write as _, fs, path::PathBuf, process};
+
+const NO_DATA: u8 = 0xFF;
+
+enum NodeKind {
+    Track {
+        anode: u8,
+        cathode: u8,
+        anode_fork: Option<u8>,
+        cathode_fork: Option<u8>,
+    },
+    Platform {
+        track: u8,
+    },
+}
+
+struct Node {
+    line: usize,
+    position: (u8, u8),
+    kind: NodeKind,
+}
+
+impl Node {
+    fn neighbors(&self) -> Vec<u8> {
+        match self.kind {
+            NodeKind::Track {
+                anode,
+                cathode,
+                anode_fork,
+                cathode_fork,
+            } => [Some(anode), Some(cathode), anode_fork, cathode_fork]
+                .into_iter()
+                .flatten()
+                .collect(),
+            NodeKind::Platform { track } => vec![track],
+        }
+    }
+
+    fn is_switch(&self) -> bool {
+        matches!(
+            self.kind,
+            NodeKind::Track { anode_fork, cathode_fork, .. } if anode_fork.is_some() || cathode_fork.is_some()
+        )
+    }
+
+    /// Packed as anode, cathode, anode fork, cathode fork, platforms repeat their track in all fields
+    fn packed(&self) -> u32 {
+        let fields = match self.kind {
+            NodeKind::Track {
+                anode,
+                cathode,
+                anode_fork,
+                cathode_fork,
+            } => [
+                anode,
+                cathode,
+                anode_fork.unwrap_or(NO_DATA),
+                cathode_fork.unwrap_or(NO_DATA),
+            ],
+            NodeKind::Platform { track } => [track; 4],
+        };
+        u32::from_be_bytes(fields)
+    }
+}
+
+struct Layout {
+    nodes: Vec<Node>,
+    switches: Vec<u8>,
+}
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let layout_path = match env::var("LIGHT_RAIL_LAYOUT") {
+        Ok(path) => manifest_dir.join(path),
+        Err(_) => manifest_dir.join("layout").join("track.txt"),
+    };
+    println!("cargo:rerun-if-env-changed=LIGHT_RAIL_LAYOUT");
+    println!("cargo:rerun-if-changed={}", layout_path.display());
+
+    let source = fs::read_to_string(&layout_path)
+        .unwrap_or_else(|err| fail(&format!("can't read {}: {}", layout_path.display(), err)));
+    let layout = parse(&source).unwrap_or_else(|err| fail(&err));
+    if let Err(errors) = validate(&layout) {
+        fail(&errors.join("\n"));
+    }
+
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("layout.rs");
+    fs::write(out_path, generate(&layout, &layout_path)).unwrap();
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("track layout error: {}", message);
+    process::exit(1);
+}
+
+fn parse_u8(token: &str, line: usize) -> Result<u8, String> {
+    token.parse().map_err(|_| {
+        format!(
+            "line {}: expected a number from 0 to 255, got '{}'",
+            line, token
+        )
+    })
+}
+
+/// Parses a neighbor with an optional fork, "12" or "12/34"
+fn parse_link(token: &str, line: usize) -> Result<(u8, Option<u8>), String> {
+    match token.split_once('/') {
+        Some((next, fork)) => Ok((parse_u8(next, line)?, Some(parse_u8(fork, line)?))),
+        None => Ok((parse_u8(token, line)?, None)),
+    }
+}
+
+fn parse(source: &str) -> Result<Layout, String> {
+    let mut nodes: Vec<(u8, Node)> = Vec::new();
+    let mut switches = None;
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.split('#').next().unwrap().trim();
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            [] => {}
+            ["switches", indices @ ..] => {
+                if switches.is_some() {
+                    return Err(format!("line {}: switches listed twice", line_number));
+                }
+                switches = Some(
+                    indices
+                        .iter()
+                        .map(|index| parse_u8(index, line_number))
+                        .collect::<Result<Vec<u8>, String>>()?,
+                );
+            }
+            [index, "track", x, y, anode, cathode] => {
+                let (anode, anode_fork) = parse_link(anode, line_number)?;
+                let (cathode, cathode_fork) = parse_link(cathode, line_number)?;
+                nodes.push((
+                    parse_u8(index, line_number)?,
+                    Node {
+                        line: line_number,
+                        position: (parse_u8(x, line_number)?, parse_u8(y, line_number)?),
+                        kind: NodeKind::Track {
+                            anode,
+                            cathode,
+                            anode_fork,
+                            cathode_fork,
+                        },
+                    },
+                ));
+            }
+            [index, "platform", x, y, track] => {
+                nodes.push((
+                    parse_u8(index, line_number)?,
+                    Node {
+                        line: line_number,
+                        position: (parse_u8(x, line_number)?, parse_u8(y, line_number)?),
+                        kind: NodeKind::Platform {
+                            track: parse_u8(track, line_number)?,
+                        },
+                    },
+                ));
+            }
+            _ => {
+                return Err(format!(
+                    "line {}: expected '<index> track <x> <y> <anode> <cathode>', \
+                     '<index> platform <x> <y> <track>' or 'switches <index>...', got '{}'",
+                    line_number, line
+                ))
+            }
+        }
+    }
+
+    // nodes are indexed by LED, so they must cover 0..count without gaps
+    nodes.sort_by_key(|(index, _)| *index);
+    for (expected, (index, node)) in nodes.iter().enumerate() {
+        if *index as usize != expected {
+            return Err(format!(
+                "line {}: node {} is out of order or duplicated, expected node {}",
+                node.line, index, expected
+            ));
+        }
+    }
+
+    Ok(Layout {
+        nodes: nodes.into_iter().map(|(_, node)| node).collect(),
+        switches: switches.ok_or("no switches line")?,
+    })
+}
+
+fn validate(layout: &Layout) -> Result<(), Vec<String>> {
+    let nodes = &layout.nodes;
+    let mut errors = Vec::new();
+    let is_track = |index: u8| {
+        nodes
+            .get(index as usize)
+            .is_some_and(|node| matches!(node.kind, NodeKind::Track { .. }))
+    };
+
+    for (index, node) in nodes.iter().enumerate() {
+        for neighbor in node.neighbors() {
+            if !is_track(neighbor) {
+                errors.push(format!(
+                    "line {}: node {} links to {}, which is not a track node",
+                    node.line, index, neighbor
+                ));
+            } else if matches!(node.kind, NodeKind::Track { .. })
+                && !nodes[neighbor as usize]
+                    .neighbors()
+                    .contains(&(index as u8))
+            {
+                errors.push(format!(
+                    "line {}: node {} links to {}, but {} doesn't link back",
+                    node.line, index, neighbor, neighbor
+                ));
+            }
+        }
+    }
+
+    let mut switch_nodes: Vec<u8> = (0..nodes.len() as u8)
+        .filter(|&index| nodes[index as usize].is_switch())
+        .collect();
+    let mut listed_switches = layout.switches.clone();
+    switch_nodes.sort();
+    listed_switches.sort();
+    if switch_nodes != listed_switches {
+        errors.push(format!(
+            "switches line lists {:?}, but the nodes with forks are {:?}",
+            layout.switches, switch_nodes
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn generate(layout: &Layout, layout_path: &std::path::Path) -> String {
+    let nodes = &layout.nodes;
+    let platforms: Vec<usize> = (0..nodes.len())
+        .filter(|&index| matches!(nodes[index].kind, NodeKind::Platform { .. }))
+        .collect();
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// generated by build.rs from {}",
+        layout_path.display()
+    )
+    .unwrap();
+    writeln!(out, "const LAYOUT_NUM_NODES: usize = {};", nodes.len()).unwrap();
+    writeln!(
+        out,
+        "const LAYOUT_NUM_PLATFORMS: usize = {};",
+        platforms.len()
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "const LAYOUT_NUM_SWITCHES: usize = {};",
+        layout.switches.len()
+    )
+    .unwrap();
+
+    writeln!(out, "const LAYOUT_NODES: [u32; {}] = [", nodes.len()).unwrap();
+    for (index, node) in nodes.iter().enumerate() {
+        let kind = match node.kind {
+            NodeKind::Platform { .. } => "platf",
+            NodeKind::Track {
+                anode_fork: Some(_),
+                cathode_fork: Some(_),
+                ..
+            } => "cross",
+            _ if node.is_switch() => "switch",
+            _ => "track",
+        };
+        writeln!(out, "    0x{:08X}, // {} {}", node.packed(), kind, index).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    writeln!(
+        out,
+        "const LAYOUT_PLATFORMS: [u8; {}] = {:?};",
+        platforms.len(),
+        platforms
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "const LAYOUT_SWITCHES: [u8; {}] = {:?};",
+        layout.switches.len(),
+        layout.switches
+    )
+    .unwrap();
+
+    let max_x = nodes.iter().map(|node| node.position.0).max().unwrap_or(0);
+    let max_y = nodes.iter().map(|node| node.position.1).max().unwrap_or(0);
+    writeln!(out, "const LAYOUT_MAX_X: u8 = {};", max_x).unwrap();
+    writeln!(out, "const LAYOUT_MAX_Y: u8 = {};", max_y).unwrap();
+    writeln!(
+        out,
+        "const LAYOUT_POSITIONS: [(u8, u8); {}] = [",
+        nodes.len()
+    )
+    .unwrap();
+    for node in nodes {
+        writeln!(out, "    {:?},", node.position).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    out
+}
@@ -0,0 +1,258 @@
+/// Hardware abstraction for the board components the game drives.
+/// Game is generic over these traits so the game logic can run on the board or against in-memory fakes on a host.
+use as1115::AS1115;
+use embedded_hal::i2c::I2c;
+use is31fl3731::{IS31FL3731, LED_COUNT};
+
+use crate::NUM_DIGITS;
+
+// IS31FL3731 registers written by PagedLeds
+const COMMAND_REGISTER: u8 = 0xFD; // selects the page of registers
+const FUNCTION_PAGE: u8 = 0x0B;
+const PICTURE_DISPLAY_REGISTER: u8 = 0x01; // frame shown, on the function page
+const DISPLAY_OPTION_REGISTER: u8 = 0x05; // blink enable and period, on the function page
+const BLINK_REGISTER: u8 = 0x12; // first LED blink control register, one bit per LED, on frame pages
+const PWM_REGISTER: u8 = 0x24; // first LED brightness register, on frame pages
+const BLINK_ENABLE: u8 = 0x08;
+const BLINK_PERIOD_MASK: u8 = 0x07; // blink period in 0.27s units, low bits of the display option
+const NO_PAGE: u8 = 0xFF;
+const BURST_SIZE: usize = 48; // pixels written in one I2C transaction
+const BLINK_MASK_SIZE: usize = LED_COUNT as usize / 8;
+
+/// Sink for the board LEDs, indexed by Location index.
+pub trait LedSink {
+    /// Sinks drawing into a hidden frame shown by show(), which need the last two ticks of changes written
+    const DOUBLE_BUFFERED: bool = false;
+
+    /// Sinks that blink pixels by themselves, see set_blink
+    const HARDWARE_BLINK: bool = false;
+
+    fn clear(&mut self);
+
+    fn set_pixel(&mut self, index: u8, brightness: u8);
+
+    /// Writes consecutive pixels from start, sinks that can burst write override this.
+    fn set_pixels(&mut self, start: u8, brightness: &[u8]) {
+        for (offset, &brightness) in brightness.iter().enumerate() {
+            self.set_pixel(start + offset as u8, brightness);
+        }
+    }
+
+    /// Shows the pixels written since the last call, for double buffered sinks.
+    fn show(&mut self) {}
+
+    /// Sets which pixels blink, one bit per pixel, for sinks with HARDWARE_BLINK.
+    /// Blinking pixels alternate between their brightness and off at the rate set by set_blink_period.
+    fn set_blink(&mut self, _mask: &[u8]) {}
+
+    /// Sets the blink period in 0.27s units, for sinks with HARDWARE_BLINK.
+    fn set_blink_period(&mut self, _period: u8) {}
+}
+
+/// Seven-segment digit display used for scores, text, and raw segment data.
+pub trait DigitDisplay {
+    fn clear(&mut self);
+
+    fn set_brightness(&mut self, level: u8);
+
+    fn display_number(&mut self, number: u16);
+
+    fn display_raw(&mut self, segments: &[u8; NUM_DIGITS as usize]);
+
+    fn display_ascii(&mut self, text: &[u8; NUM_DIGITS as usize]);
+}
+
+/// Source of raw button states, debouncing and events are handled by BoardInput.
+pub trait ButtonSource {
+    fn is_pressed(&mut self, index: usize) -> bool;
+}
+
+/// Buzzer for button and game tones.
+pub trait Buzzer {
+    fn tone(&mut self, frequency: u16, duration: u16);
+
+    fn no_tone(&mut self);
+}
+
+/// Non-volatile byte storage for settings.
+pub trait Storage {
+    fn read_byte(&self, offset: u16) -> u8;
+
+    fn write_byte(&mut self, offset: u16, data: u8);
+
+    /// Writes the byte only if it changed, saving a write cycle.
+    fn update_byte(&mut self, offset: u16, data: u8) {
+        if self.read_byte(offset) != data {
+            self.write_byte(offset, data);
+        }
+    }
+}
+
+impl<I2C> LedSink for IS31FL3731<I2C>
+where
+    I2C: I2c,
+{
+    fn clear(&mut self) {
+        self.clear_blocking().ok();
+    }
+
+    fn set_pixel(&mut self, index: u8, brightness: u8) {
+        self.pixel_blocking(index, brightness).ok();
+    }
+}
+
+/// IS31FL3731 LEDs written as bursts of PWM registers, with PAGE_FLIP alternating between two frames of
+/// the driver so a tick's changes show at once. Blinking pixels use the driver's blink control, which blinks
+/// every LED with its bit set at one period. Its auto-breath fades the whole frame rather than single LEDs,
+/// so fades are drawn in software. The IS31FL3731 driver handles setup, this writes the frame registers
+/// directly over its own handle to the I2C bus.
+pub struct PagedLeds<I2C, const PAGE_FLIP: bool> {
+    i2c: I2C,
+    address: u8,
+    back_frame: u8, // frame written to, the shown frame when not page flipping
+    page: u8,       // selected register page, nothing else writes to the IS31FL3731 after setup
+}
+
+impl<I2C, const PAGE_FLIP: bool> PagedLeds<I2C, PAGE_FLIP>
+where
+    I2C: I2c,
+{
+    /// Takes a handle to the I2C bus of an IS31FL3731 already set up by the driver, showing frame 0
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        Self {
+            i2c,
+            address,
+            back_frame: if PAGE_FLIP { 1 } else { 0 },
+            page: NO_PAGE, // whatever the driver selected last
+        }
+    }
+
+    fn select_page(&mut self, page: u8) {
+        if self.page != page {
+            self.page = page;
+            self.i2c.write(self.address, &[COMMAND_REGISTER, page]).ok();
+        }
+    }
+
+    fn write_pwm(&mut self, start: u8, brightness: &[u8]) {
+        let mut buffer = [0u8; BURST_SIZE + 1];
+        for (chunk_index, chunk) in brightness.chunks(BURST_SIZE).enumerate() {
+            // the register address auto increments while writing
+            buffer[0] = PWM_REGISTER + start + (chunk_index * BURST_SIZE) as u8;
+            buffer[1..=chunk.len()].copy_from_slice(chunk);
+            self.i2c.write(self.address, &buffer[..=chunk.len()]).ok();
+        }
+    }
+
+    fn write_blink(&mut self, mask: &[u8]) {
+        let mut buffer = [0u8; BLINK_MASK_SIZE + 1];
+        buffer[0] = BLINK_REGISTER;
+        buffer[1..=mask.len()].copy_from_slice(mask);
+        self.i2c.write(self.address, &buffer[..=mask.len()]).ok();
+    }
+}
+
+impl<I2C, const PAGE_FLIP: bool> LedSink for PagedLeds<I2C, PAGE_FLIP>
+where
+    I2C: I2c,
+{
+    const DOUBLE_BUFFERED: bool = PAGE_FLIP;
+    const HARDWARE_BLINK: bool = true;
+
+    fn clear(&mut self) {
+        let zeros = [0u8; LED_COUNT as usize];
+        for frame in 0..=1 {
+            self.select_page(frame);
+            self.write_pwm(0, &zeros);
+            self.write_blink(&zeros[..BLINK_MASK_SIZE]);
+        }
+    }
+
+    fn set_pixel(&mut self, index: u8, brightness: u8) {
+        self.set_pixels(index, &[brightness]);
+    }
+
+    fn set_pixels(&mut self, start: u8, brightness: &[u8]) {
+        self.select_page(self.back_frame);
+        self.write_pwm(start, brightness);
+    }
+
+    fn set_blink(&mut self, mask: &[u8]) {
+        self.select_page(self.back_frame);
+        self.write_blink(mask);
+    }
+
+    fn set_blink_period(&mut self, period: u8) {
+        // only LEDs with their blink bit set blink
+        let option = BLINK_ENABLE | (period & BLINK_PERIOD_MASK);
+        self.select_page(FUNCTION_PAGE);
+        self.i2c
+            .write(self.address, &[DISPLAY_OPTION_REGISTER, option])
+            .ok();
+    }
+
+    fn show(&mut self) {
+        if PAGE_FLIP {
+            self.select_page(FUNCTION_PAGE);
+            self.i2c
+                .write(self.address, &[PICTURE_DISPLAY_REGISTER, self.back_frame])
+                .ok();
+            self.back_frame ^= 1;
+        }
+    }
+}
+
+impl<I2C> DigitDisplay for AS1115<I2C, NUM_DIGITS>
+where
+    I2C: I2c,
+{
+    fn clear(&mut self) {
+        AS1115::clear(self).ok();
+    }
+
+    fn set_brightness(&mut self, level: u8) {
+        self.set_intensity(level).ok();
+    }
+
+    fn display_number(&mut self, number: u16) {
+        AS1115::display_number(self, number).ok();
+    }
+
+    fn display_raw(&mut self, segments: &[u8; NUM_DIGITS as usize]) {
+        AS1115::display_raw(self, segments).ok();
+    }
+
+    fn display_ascii(&mut self, text: &[u8; NUM_DIGITS as usize]) {
+        AS1115::display_ascii(self, text).ok();
+    }
+}
+
+#[cfg(target_arch = "avr")]
+mod avr {
+    use atmega_hal::{
+        eeprom::Eeprom,
+        port::{
+            mode::{Input, PullUp},
+            Dynamic, Pin,
+        },
+    };
+
+    use super::{ButtonSource, Storage};
+    use crate::NUM_BUTTONS;
+
+    impl ButtonSource for [Pin<Input<PullUp>, Dynamic>; NUM_BUTTONS] {
+        fn is_pressed(&mut self, index: usize) -> bool {
+            self[index].is_low()
+        }
+    }
+
+    impl Storage for Eeprom {
+        fn read_byte(&self, offset: u16) -> u8 {
+            Eeprom::read_byte(self, offset)
+        }
+
+        fn write_byte(&mut self, offset: u16, data: u8) {
+            Eeprom::write_byte(self, offset, data)
+        }
+    }
+}
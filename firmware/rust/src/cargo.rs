@@ -39,9 +39,10 @@ impl LedPattern {
     pub fn get_pwm(&self, phase: u8, min_b: u8, max_b: u8) -> u8 {
         match self {
             LedPattern::Solid => max_b,
+            // one even blink fully off, which the LED driver can blink by itself
             LedPattern::Blink1 => match phase % 128 {
-                0..=23 => min_b, // 24 ticks off
-                _ => max_b,
+                0..=63 => 0, // 64 ticks off
+                _ => min_b.max(max_b),
             },
             LedPattern::Blink2 => match phase % 128 {
                 0..=15 => min_b,   // 16 ticks off
@@ -68,4 +69,14 @@ impl LedPattern {
             }
         }
     }
+
+    /// Returns the brightness the LED driver blinks for patterns it reproduces exactly, an even blink fully
+    /// off once a cycle, None for patterns that get_pwm draws each tick. The driver's blink timer runs by
+    /// itself, so only the phase of the blink differs.
+    pub fn hardware_blink(&self, min_b: u8, max_b: u8) -> Option<u8> {
+        match self {
+            LedPattern::Blink1 => Some(min_b.max(max_b)),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,66 @@
+/// Millisecond timebase and fixed-rate scheduling of the game loop.
+/// The firmware advances the clock from a 1 ms timer interrupt, host builds advance it themselves.
+use core::cell::Cell;
+use critical_section::Mutex;
+
+pub const TICK_MS: u32 = 10; // period of Game::tick
+const MAX_CATCH_UP: u8 = 4; // late ticks run back to back before the scheduler skips ahead
+
+// milliseconds since boot, wraps after ~49 days
+static MILLIS: Mutex<Cell<u32>> = Mutex::new(Cell::new(0));
+
+/// Returns the milliseconds since boot, compare times with wrapping_sub as the clock wraps after ~49 days
+pub fn now() -> u32 {
+    critical_section::with(|cs| MILLIS.borrow(cs).get())
+}
+
+/// Advances the clock, called from the timer interrupt
+pub fn advance(ms: u32) {
+    critical_section::with(|cs| {
+        let millis = MILLIS.borrow(cs);
+        millis.set(millis.get().wrapping_add(ms));
+    });
+}
+
+/// Runs a task at a fixed rate from the clock, independent of how long each run takes.
+/// Runs that are late are caught up back to back, up to MAX_CATCH_UP at once, runs further behind are
+/// skipped and counted as overruns.
+pub struct Scheduler {
+    period: u32,
+    next: u32,     // time the next run is due
+    overruns: u32, // runs skipped because the task fell too far behind
+}
+
+impl Scheduler {
+    /// Creates a scheduler running every period_ms, the first run is due immediately
+    pub fn new(period_ms: u32) -> Self {
+        Self {
+            period: period_ms,
+            next: now(),
+            overruns: 0,
+        }
+    }
+
+    /// Returns the number of runs due now and schedules the following ones
+    pub fn due(&mut self) -> u8 {
+        let now = now();
+        let mut due = 0;
+        // due while next isn't in the future, wrapping safe
+        while (now.wrapping_sub(self.next) as i32) >= 0 {
+            if due == MAX_CATCH_UP {
+                let skipped = now.wrapping_sub(self.next) / self.period + 1;
+                self.overruns = self.overruns.saturating_add(skipped);
+                self.next = self.next.wrapping_add(skipped * self.period);
+                break;
+            }
+            due += 1;
+            self.next = self.next.wrapping_add(self.period);
+        }
+        due
+    }
+
+    /// Returns the number of runs skipped since the scheduler was created
+    pub fn overruns(&self) -> u32 {
+        self.overruns
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{
+    game_settings::GameSettings,
+    input::InputDirection,
+    location::{Location, BOARD_MAX_X, BOARD_MAX_Y, NUM_LOCATION_NODES},
+};
+
+const EFFECT_SPEED: u16 = 4; // distance the effect front moves per tick, half millimeters or angle units
+const EFFECT_WIDTH: u16 = 32; // width of the lit band behind the front, fades out to the back
+const SWEEP_TURN: u16 = 256; // angle units in a full turn of a sweep
+
+/// Shape of a board-wide effect, each maps an LED position to the distance the effect front travels to reach it
+#[derive(Clone, Copy)]
+enum Shape {
+    Wipe(InputDirection),
+    Ripple(u8, u8),
+    Sweep,
+}
+
+/// Board-wide LED animation drawn from the physical LED positions.
+/// A band of light travels across the board from a side, a point, or around the board center, fading behind
+/// its front. Played by Game on a layer over the game entities, which show through where they are brighter.
+#[derive(Clone, Copy)]
+pub struct Effect {
+    shape: Shape,
+    front: u16, // distance travelled by the front of the band
+    end: u16,   // distance travelled when the band has left the board
+}
+
+impl Effect {
+    /// Band of light crossing the board in the given direction
+    pub fn wipe(direction: InputDirection) -> Self {
+        let end = match direction {
+            InputDirection::Left | InputDirection::Right => BOARD_MAX_X,
+            InputDirection::Up | InputDirection::Down => BOARD_MAX_Y,
+        };
+        Self::new(Shape::Wipe(direction), end as u16)
+    }
+
+    /// Ring of light spreading out from the location, e.g. a collision or a delivered platform
+    pub fn ripple(origin: Location) -> Self {
+        let (x, y) = origin.position();
+        let end = [
+            (0, 0),
+            (BOARD_MAX_X, 0),
+            (0, BOARD_MAX_Y),
+            (BOARD_MAX_X, BOARD_MAX_Y),
+        ]
+        .into_iter()
+        .map(|corner| distance((x, y), corner))
+        .max()
+        .unwrap();
+        Self::new(Shape::Ripple(x, y), end)
+    }
+
+    /// Beam of light turning once around the board center, clockwise from the right
+    pub fn sweep() -> Self {
+        Self::new(Shape::Sweep, SWEEP_TURN)
+    }
+
+    fn new(shape: Shape, end: u16) -> Self {
+        Self {
+            shape,
+            front: 0,
+            end: end + EFFECT_WIDTH,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.front > self.end
+    }
+
+    /// Draws the current frame and advances the effect, only LEDs that changed since the last frame are updated
+    pub fn render<F>(&mut self, settings: &GameSettings, mut update_callback: F, force_update: bool)
+    where
+        F: FnMut(Location, u8),
+    {
+        // the first frame draws every LED, replacing any effect played before
+        let force_update = force_update || self.front == 0;
+        let max_brightness = settings.car_brightness();
+        for index in 0..NUM_LOCATION_NODES {
+            let location = Location::new(index as u8);
+            let reach = self.reach(location.position());
+            let brightness = Self::band_brightness(reach, self.front, max_brightness);
+            if force_update
+                || brightness
+                    != Self::band_brightness(reach, self.front - EFFECT_SPEED, max_brightness)
+            {
+                update_callback(location, brightness);
+            }
+        }
+        self.front += EFFECT_SPEED;
+    }
+
+    /// Returns the distance the front travels before reaching the position
+    fn reach(&self, (x, y): (u8, u8)) -> u16 {
+        match self.shape {
+            Shape::Wipe(InputDirection::Right) => x as u16,
+            Shape::Wipe(InputDirection::Left) => (BOARD_MAX_X - x) as u16,
+            Shape::Wipe(InputDirection::Down) => y as u16,
+            Shape::Wipe(InputDirection::Up) => (BOARD_MAX_Y - y) as u16,
+            Shape::Ripple(origin_x, origin_y) => distance((x, y), (origin_x, origin_y)),
+            Shape::Sweep => angle(
+                x as i16 - (BOARD_MAX_X / 2) as i16,
+                y as i16 - (BOARD_MAX_Y / 2) as i16,
+            ),
+        }
+    }
+
+    /// Brightness of a position in the band, full at the front and fading out over the band width
+    #[inline(always)]
+    fn band_brightness(reach: u16, front: u16, max_brightness: u8) -> u8 {
+        match front.checked_sub(reach) {
+            Some(behind) if behind < EFFECT_WIDTH => {
+                let fade = (EFFECT_WIDTH - behind) * (256 / EFFECT_WIDTH) - 1;
+                ((fade * max_brightness as u16) >> 8) as u8
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// Approximate distance between positions, within 7% without a square root
+fn distance(a: (u8, u8), b: (u8, u8)) -> u16 {
+    let dx = a.0.abs_diff(b.0) as u16;
+    let dy = a.1.abs_diff(b.1) as u16;
+    let (min, max) = if dx < dy { (dx, dy) } else { (dy, dx) };
+    max + ((min * 3) >> 3)
+}
+
+/// Approximate clockwise angle of the offset from the x axis (y points down), a full turn is SWEEP_TURN units.
+/// Linear within each octant, so a sweep speeds up slightly between the diagonals.
+fn angle(dx: i16, dy: i16) -> u16 {
+    let x = dx.unsigned_abs();
+    let y = dy.unsigned_abs();
+    let octant = SWEEP_TURN / 8;
+    let quadrant_angle = if x == 0 && y == 0 {
+        0
+    } else if y <= x {
+        octant * y / x
+    } else {
+        2 * octant - octant * x / y
+    };
+    match (dx >= 0, dy >= 0) {
+        (true, true) => quadrant_angle,
+        (false, true) => SWEEP_TURN / 2 - quadrant_angle,
+        (false, false) => SWEEP_TURN / 2 + quadrant_angle,
+        (true, false) => SWEEP_TURN - quadrant_angle,
+    }
+}
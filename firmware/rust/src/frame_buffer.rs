@@ -0,0 +1,176 @@
+use crate::{board::LedSink, location::NUM_LOCATION_NODES};
+
+const DIRTY_SIZE: usize = NUM_LOCATION_NODES.div_ceil(8);
+const MAX_GAP: usize = 4; // clean pixels rewritten to join two dirty runs, cheaper than a new I2C write
+const SHOWN_CHUNK: usize = 48; // shown pixels composed at a time for the sink
+
+/// Board LED brightness that entities draw into during a tick, written to the LED sink once per tick.
+/// Only pixels that changed are written, consecutive ones in a single burst, so redraws of unchanged
+/// entities cost nothing. Double buffered sinks also get the pixels changed in the previous tick, as
+/// those are missing from the frame they draw into next. Sinks with HARDWARE_BLINK get the blink bits
+/// and blink period only when they change.
+/// Board-wide effects draw into a layer of their own, each LED shows the brighter of the two layers.
+pub struct FrameBuffer {
+    pixels: [u8; NUM_LOCATION_NODES],
+    effect: [u8; NUM_LOCATION_NODES], // effect layer, shown over the pixels where it is brighter
+    dirty: [u8; DIRTY_SIZE],          // pixels changed this tick
+    last_dirty: [u8; DIRTY_SIZE],     // pixels changed in the previous tick
+    blink: [u8; DIRTY_SIZE],          // pixels blinking
+    shown_blink: [u8; DIRTY_SIZE],    // pixels blinking and not covered by the effect
+    blink_changed: bool,              // shown blink bits changed this tick
+    last_blink_changed: bool,         // blink bits changed in the previous tick
+    blink_period: u8,                 // blink period set by the game
+    sink_blink_period: u8,            // blink period written to the sink, 0 before the first write
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self {
+            pixels: [0; NUM_LOCATION_NODES],
+            effect: [0; NUM_LOCATION_NODES],
+            dirty: [0; DIRTY_SIZE],
+            last_dirty: [0; DIRTY_SIZE],
+            blink: [0; DIRTY_SIZE],
+            shown_blink: [0; DIRTY_SIZE],
+            blink_changed: false,
+            last_blink_changed: false,
+            blink_period: 0,
+            sink_blink_period: 0,
+        }
+    }
+}
+
+impl FrameBuffer {
+    pub fn pixel(&self, index: u8) -> u8 {
+        self.pixels[index as usize]
+    }
+
+    /// Returns the brightness the LED shows, the brighter of the pixel and the effect
+    fn shown(&self, index: usize) -> u8 {
+        self.pixels[index].max(self.effect[index])
+    }
+
+    /// Draws a steady pixel, stopping it from blinking
+    pub fn set_pixel(&mut self, index: u8, brightness: u8) {
+        self.set_pixel_blink(index, brightness, false);
+    }
+
+    /// Draws a pixel, blinking ones alternate between the brightness and off on sinks with HARDWARE_BLINK
+    pub fn set_pixel_blink(&mut self, index: u8, brightness: u8, blink: bool) {
+        let index = index as usize;
+        let shown = self.shown(index);
+        self.pixels[index] = brightness;
+        let bit = 1 << (index % 8);
+        if blink {
+            self.blink[index / 8] |= bit;
+        } else {
+            self.blink[index / 8] &= !bit;
+        }
+        self.update_shown(index, shown);
+    }
+
+    /// Draws a pixel of the effect layer
+    pub fn set_effect_pixel(&mut self, index: u8, brightness: u8) {
+        let index = index as usize;
+        let shown = self.shown(index);
+        self.effect[index] = brightness;
+        self.update_shown(index, shown);
+    }
+
+    /// Clears the effect layer, showing the pixels under it
+    pub fn clear_effect(&mut self) {
+        for index in 0..NUM_LOCATION_NODES as u8 {
+            self.set_effect_pixel(index, 0);
+        }
+    }
+
+    /// Marks the pixel dirty if the shown brightness changed, and updates its shown blink bit.
+    /// Blinking pixels under a brighter effect hold steady, so the effect doesn't blink.
+    fn update_shown(&mut self, index: usize, last_shown: u8) {
+        let bit = 1 << (index % 8);
+        if self.shown(index) != last_shown {
+            self.dirty[index / 8] |= bit;
+        }
+
+        let blink = self.blink[index / 8] & bit != 0 && self.effect[index] <= self.pixels[index];
+        if (self.shown_blink[index / 8] & bit != 0) != blink {
+            self.shown_blink[index / 8] ^= bit;
+            self.blink_changed = true;
+        }
+    }
+
+    /// Sets the period blinking pixels blink at, in 0.27s units, on sinks with HARDWARE_BLINK
+    pub fn set_blink_period(&mut self, period: u8) {
+        self.blink_period = period;
+    }
+
+    pub fn clear(&mut self) {
+        for index in 0..NUM_LOCATION_NODES as u8 {
+            self.set_pixel(index, 0);
+            self.set_effect_pixel(index, 0);
+        }
+    }
+
+    /// Writes the changed pixels and blink bits to the sink and shows them
+    pub fn flush<L: LedSink>(&mut self, leds: &mut L) {
+        let mut pending = self.dirty;
+        if L::DOUBLE_BUFFERED {
+            for (pending, last_dirty) in pending.iter_mut().zip(self.last_dirty.iter()) {
+                *pending |= last_dirty;
+            }
+        }
+        self.last_dirty = self.dirty;
+        self.dirty = [0; DIRTY_SIZE];
+
+        let is_pending = |index: usize| pending[index / 8] & (1 << (index % 8)) != 0;
+        let mut written = false;
+        let mut index = 0;
+        while index < NUM_LOCATION_NODES {
+            if !is_pending(index) {
+                index += 1;
+                continue;
+            }
+
+            // extend the run over pending pixels and short gaps of clean ones
+            let start = index;
+            let mut end = index + 1;
+            let mut scan = end;
+            while scan < NUM_LOCATION_NODES && scan - end <= MAX_GAP {
+                if is_pending(scan) {
+                    end = scan + 1;
+                }
+                scan += 1;
+            }
+
+            let mut shown = [0u8; SHOWN_CHUNK];
+            for chunk_start in (start..end).step_by(SHOWN_CHUNK) {
+                let chunk_end = (chunk_start + SHOWN_CHUNK).min(end);
+                for (index, brightness) in (chunk_start..chunk_end).zip(shown.iter_mut()) {
+                    *brightness = self.shown(index);
+                }
+                leds.set_pixels(chunk_start as u8, &shown[..chunk_end - chunk_start]);
+            }
+            written = true;
+            index = end;
+        }
+
+        if L::HARDWARE_BLINK
+            && (self.blink_changed || (L::DOUBLE_BUFFERED && self.last_blink_changed))
+        {
+            leds.set_blink(&self.shown_blink);
+            written = true;
+        }
+        self.last_blink_changed = self.blink_changed;
+        self.blink_changed = false;
+
+        // the period is shared by both frames, so it is written once
+        if L::HARDWARE_BLINK && self.blink_period != self.sink_blink_period {
+            leds.set_blink_period(self.blink_period);
+            self.sink_blink_period = self.blink_period;
+        }
+
+        if written {
+            leds.show();
+        }
+    }
+}
@@ -1,30 +1,35 @@
-use as1115::AS1115;
-use embedded_hal::i2c::I2c;
-use heapless::Vec;
-use is31fl3731::{gamma, IS31FL3731};
+use is31fl3731::gamma;
 
 use crate::{
+    board::{ButtonSource, Buzzer, DigitDisplay, LedSink, Storage},
+    effect::Effect,
+    frame_buffer::FrameBuffer,
     game_settings::GameSettings,
     game_state::*,
-    input::{BoardInput, InputDirection, InputEvent},
+    high_scores::HighScores,
+    input::{BoardInput, InputDirection, InputEvent, CHORD_LEFT_RIGHT},
     location::Location,
     modes::*,
     platform::Platform,
     switch::Switch,
-    tone::TimerTone,
-    train::{Car, Train},
-    NUM_DIGITS,
+    train::{Collision, Trains},
 };
 
-pub struct Game<I2C>
+pub struct Game<BUZZER, DIGITS, BUTTONS, LEDS, EEPROM>
 where
-    I2C: I2c,
+    BUZZER: Buzzer,
+    DIGITS: DigitDisplay,
+    BUTTONS: ButtonSource,
+    LEDS: LedSink,
+    EEPROM: Storage,
 {
     // board components
-    board_buzzer: TimerTone,
-    board_digits: AS1115<I2C, NUM_DIGITS>,
-    board_input: BoardInput,
-    board_leds: IS31FL3731<I2C>,
+    board_buzzer: BUZZER,
+    board_digits: DIGITS,
+    board_input: BoardInput<BUTTONS>,
+    board_leds: LEDS,
+    board_eeprom: EEPROM,
+    frame: FrameBuffer, // LEDs drawn during a tick, written to board_leds once at the end
 
     // game mode state
     mode_index: usize,
@@ -35,30 +40,37 @@ where
     state: GameState,
 }
 
-impl<I2C> Game<I2C>
+impl<BUZZER, DIGITS, BUTTONS, LEDS, EEPROM> Game<BUZZER, DIGITS, BUTTONS, LEDS, EEPROM>
 where
-    I2C: I2c,
+    BUZZER: Buzzer,
+    DIGITS: DigitDisplay,
+    BUTTONS: ButtonSource,
+    LEDS: LedSink,
+    EEPROM: Storage,
 {
     pub fn new(
-        board_buzzer: TimerTone,
-        board_digits: AS1115<I2C, NUM_DIGITS>,
-        board_input: BoardInput,
-        board_leds: IS31FL3731<I2C>,
-        cars: [Car; MAX_CARS],
+        board_buzzer: BUZZER,
+        board_digits: DIGITS,
+        board_input: BoardInput<BUTTONS>,
+        board_leds: LEDS,
+        board_eeprom: EEPROM,
         settings: GameSettings,
     ) -> Self {
         let platforms = Platform::take();
         let switches = Switch::take();
-        let trains = Vec::<Train, MAX_TRAINS>::new();
+        let trains = Trains::default();
+        let high_scores = HighScores::new(&board_eeprom);
 
         let state = GameState {
             target_mode_index: 0,
             is_paused: false,
             is_over: false,
             redraw: false,
+            effect: Some(Effect::sweep()), // boot animation
+            train_style: None,
             display: DisplayState::None,
             settings,
-            cars,
+            high_scores,
             trains,
             platforms,
             switches,
@@ -69,6 +81,8 @@ where
             board_digits,
             board_input,
             board_leds,
+            board_eeprom,
+            frame: FrameBuffer::default(),
             mode_index: 0,
             mode: GameMode::default(),
             last_display: DisplayState::None,
@@ -76,16 +90,44 @@ where
         }
     }
 
+    /// Returns the game state, used by host builds to inspect the running game
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Returns the mutable game state, used by host builds to drive the running game
+    pub fn state_mut(&mut self) -> &mut GameState {
+        &mut self.state
+    }
+
+    pub fn board_digits(&self) -> &DIGITS {
+        &self.board_digits
+    }
+
+    pub fn board_input_mut(&mut self) -> &mut BoardInput<BUTTONS> {
+        &mut self.board_input
+    }
+
+    pub fn board_leds(&self) -> &LEDS {
+        &self.board_leds
+    }
+
     pub fn restart(&mut self) {
-        self.board_digits.display_number(1).ok();
+        self.board_digits.display_number(1);
         self.mode = GameMode::from_index(self.mode_index);
+        self.state.train_style = None;
         self.mode.on_restart(&mut self.state);
         self.state.redraw = true;
     }
 
     pub fn tick(&mut self) {
+        self.update();
+        self.frame.flush(&mut self.board_leds);
+    }
+
+    fn update(&mut self) {
         // handle input events, some events are shared betweens all modes
-        if let Some(event) = self.board_input.update() {
+        for event in self.board_input.update() {
             match event {
                 // toggle switches
                 InputEvent::SwitchButtonPressed(index) => {
@@ -118,7 +160,7 @@ where
                     }
                     // save settings when exiting from settings mode
                     if matches!(self.mode, GameMode::SettingsMode(_)) {
-                        self.state.settings.save();
+                        self.state.settings.save(&mut self.board_eeprom);
                     }
                     self.state.target_mode_index = 0;
                     self.mode_index = 0;
@@ -127,6 +169,14 @@ where
                     self.restart();
                     //return;
                 }
+                // mute or unmute, kept over power cycles like the other settings
+                InputEvent::ChordPressed(CHORD_LEFT_RIGHT) => {
+                    self.state.settings.toggle_buzzer();
+                    self.state.settings.save(&mut self.board_eeprom);
+                    if self.state.settings.is_buzzer_enabled() {
+                        self.board_buzzer.tone(3500, 30);
+                    }
+                }
                 _ => {}
             }
 
@@ -141,67 +191,114 @@ where
 
         self.mode.on_game_tick(&mut self.state);
 
+        // persist records set by modes when a game ends
+        if self.state.high_scores.is_dirty() {
+            self.state.high_scores.save(&mut self.board_eeprom);
+        }
+
         // update board digits/score display
         if self.last_display != self.state.display {
             self.last_display = self.state.display;
 
             // TODO: flag or something to avoid calling unnecessarily
-            self.board_digits
-                .set_intensity(self.state.settings.digit_brightness_level())
-                .ok();
+            self.board_digits.set_brightness(self.state.settings.digit_brightness_level());
 
             match self.state.display {
                 DisplayState::None => {
-                    self.board_digits.clear().ok();
+                    self.board_digits.clear();
                 }
                 DisplayState::Score(score) => {
-                    self.board_digits.display_number(score).ok();
+                    self.board_digits.display_number(score);
                 }
                 DisplayState::Segments(ref segments) => {
-                    self.board_digits.display_raw(segments).ok();
+                    self.board_digits.display_raw(segments);
                 }
                 DisplayState::Text(ref text) => {
-                    self.board_digits.display_ascii(text).ok();
+                    self.board_digits.display_ascii(text);
                 }
             }
         }
 
+        // entities show through again once an effect is done
+        if self.state.effect.is_some_and(|effect| effect.is_done()) {
+            self.state.effect = None;
+            self.frame.clear_effect();
+        }
+
         // clear board LEDs and force update all entities when requested
         if self.state.redraw {
-            self.board_leds.clear_blocking().ok();
+            self.frame.clear();
+        }
+
+        // board-wide effects are drawn on a layer over the entities, which keep drawing underneath
+        if let Some(effect) = self.state.effect.as_mut() {
+            effect.render(
+                &self.state.settings,
+                |location, brightness| {
+                    self.frame
+                        .set_effect_pixel(location.index(), gamma(brightness));
+                },
+                self.state.redraw,
+            );
         }
 
-        // skip updating game entities if game is paused or over
+        // skip updating game entities if game is paused or over, redraws show them as they were last drawn
         if self.state.is_over || self.state.is_paused {
+            if self.state.redraw {
+                self.redraw_frozen_entities();
+            }
+            self.state.redraw = false;
             return;
         }
 
         // helper closure to update entity LEDs
         let mut do_led_update = |location: Location, brightness: u8| {
-            self.board_leds
-                .pixel_blocking(location.index(), gamma(brightness))
-                .ok();
+            self.frame.set_pixel(location.index(), gamma(brightness));
         };
 
         // update train, platform, and switch entities
         let mut event_indices = heapless::Vec::<usize, MAX_TRAINS>::new();
-        for (train_index, train) in self.state.trains.iter_mut().enumerate() {
+        let train_style = self.state.train_style();
+        for train_index in 0..self.state.trains.len() {
+            let mut train = self.state.trains.get_mut(train_index).unwrap();
             if train.advance(
                 &self.state.settings,
                 &self.state.switches,
+                train_style,
                 &mut do_led_update,
                 self.state.redraw,
             ) {
                 event_indices.push(train_index).ok();
             }
         }
+
+        // detect collisions once all trains moved, so trains moving away in the same tick don't collide
+        let mut collisions = heapless::Vec::<Collision, MAX_TRAINS>::new();
         for &train_index in event_indices.iter() {
-            self.mode.on_train_advance(train_index, &mut self.state);
+            if let Some(collision) = self.state.trains.collision(train_index) {
+                // head-on collisions of two moving trains are detected from both sides
+                if !collisions
+                    .iter()
+                    .any(|c| c.involves(collision.train_index, collision.other_index))
+                {
+                    collisions.push(collision).ok();
+                }
+            }
+        }
+        for &collision in collisions.iter() {
+            if self.state.is_over {
+                break;
+            }
+            self.mode.on_collision(collision, &mut self.state);
         }
 
-        for platform in self.state.platforms.iter_mut() {
-            platform.update(&self.state.settings, &mut do_led_update, self.state.redraw);
+        for &train_index in event_indices.iter() {
+            if self.state.is_over {
+                break;
+            }
+            self.mode.on_train_advance(train_index, &mut self.state);
         }
+
         for switch in self.state.switches.iter_mut() {
             switch.update(
                 &self.state.settings,
@@ -211,6 +308,64 @@ where
             );
         }
 
+        // platforms with patterns the LED driver can blink are only drawn when they change,
+        // and the driver blinks them at the game speed
+        if let Some(period) = self.state.settings.blink_period() {
+            self.frame.set_blink_period(period);
+        }
+        let mut do_platform_update = |location: Location, brightness: u8, blink: bool| {
+            self.frame
+                .set_pixel_blink(location.index(), gamma(brightness), blink);
+        };
+        for platform in self.state.platforms.iter_mut() {
+            platform.update(
+                &self.state.settings,
+                &mut do_platform_update,
+                self.state.redraw,
+                LEDS::HARDWARE_BLINK,
+            );
+        }
+
         self.state.redraw = false;
     }
+
+    /// Draws the entities as they were last drawn, used while they are frozen
+    fn redraw_frozen_entities(&mut self) {
+        for train in self.state.trains.iter() {
+            for car in train.cars() {
+                self.frame.set_pixel(car.loc.index(), gamma(car.last_brightness));
+            }
+            if let Some((lead_loc, brightness)) = train.lead() {
+                self.frame.set_pixel(lead_loc.index(), gamma(brightness));
+            }
+            for &(trail_loc, brightness) in train.trail() {
+                self.frame.set_pixel(trail_loc.index(), gamma(brightness));
+            }
+        }
+
+        let mut do_led_update = |location: Location, brightness: u8| {
+            self.frame.set_pixel(location.index(), gamma(brightness));
+        };
+        for switch in self.state.switches.iter_mut() {
+            switch.update(
+                &self.state.settings,
+                &self.state.trains,
+                &mut do_led_update,
+                true,
+            );
+        }
+
+        let mut do_platform_update = |location: Location, brightness: u8, blink: bool| {
+            self.frame
+                .set_pixel_blink(location.index(), gamma(brightness), blink);
+        };
+        for platform in self.state.platforms.iter_mut() {
+            platform.update(
+                &self.state.settings,
+                &mut do_platform_update,
+                true,
+                LEDS::HARDWARE_BLINK,
+            );
+        }
+    }
 }
@@ -1,6 +1,9 @@
-use crate::{
+use heapless::Vec;
 
-    Eeprom,
+use crate::{
+    board::Storage,
+    record_ring::{RecordError, RecordRing},
+    train::{TrainStyle, NUM_TRAIN_STYLES},
 };
 
 const DIGITS_MAX_BRIGHTNESS: u8 = 9; //as1115::constants::MAX_INTENSITY;
@@ -8,60 +11,172 @@ const LED_BRIGHTNESS_LEVELS: u8 = 6; // 6 levels of brightness between 0 and 255
 
 const RED_BRIGHTNESS_LEVELS: [u8; LED_BRIGHTNESS_LEVELS as usize] = [0, 28, 37, 60, 90, 127];
 const YEL_BRIGHTNESS_LEVELS: [u8; LED_BRIGHTNESS_LEVELS as usize] = [0, 50, 100, 150, 200, 255];
+const GAME_SPEED_LEVELS: u8 = 5;
+const GAME_SPEED_SCALES: [u8; GAME_SPEED_LEVELS as usize] = [50, 75, 100, 125, 150]; // percent of normal speed
+const DEFAULT_GAME_SPEED_LEVEL: u8 = 2; // normal speed
+const BLINK_PERIODS: [u8; GAME_SPEED_LEVELS as usize] = [0, 6, 5, 4, 3]; // LED driver blinks per pattern cycle, 0.27s units
+const STATION_DWELL_LEVELS: u8 = 5; // off, then longer dwells
+const STATION_DWELL_STEP: u8 = 50; // ticks a train waits at a station per dwell level
+
+// settings record: version followed by the payload, stored in a wear-leveled ring with a CRC
+const SETTINGS_VERSION: u8 = 1; // bump when a field changes meaning, and migrate older records
+const MAX_PAYLOAD_SIZE: usize = 16; // room for the layout to grow
+const RECORD_SIZE: usize = 1 + MAX_PAYLOAD_SIZE;
+const SETTINGS_SLOTS: u8 = 16;
+// after the unversioned layout at 0..5 so it can be migrated, ends before the high scores at 0x200
+const SETTINGS_RING: RecordRing<RECORD_SIZE> = RecordRing::new(0x10, SETTINGS_SLOTS);
+const _: () = assert!(SETTINGS_RING.end() <= 0x200);
+
+// payload fields, new fields are appended and read as their default from shorter records
+const DIGIT_BRIGHTNESS_FIELD: usize = 0;
+const CAR_BRIGHTNESS_FIELD: usize = 1;
+const PLATFORM_BRIGHTNESS_FIELD: usize = 2;
+const SWITCH_BRIGHTNESS_FIELD: usize = 3;
+const BUZZER_ENABLED_FIELD: usize = 4;
+const GAME_SPEED_FIELD: usize = 5;
+const SMOOTH_MOTION_FIELD: usize = 6;
+const TRAIN_STYLE_FIELD: usize = 7;
+const STATION_DWELL_FIELD: usize = 8;
+const PAYLOAD_SIZE: usize = 9;
+
+const LEGACY_OFFSET: u16 = 0; // unversioned layout, same fields as version 1
+const LEGACY_SIZE: usize = 5;
 
 pub struct GameSettings {
-    eeprom: Eeprom,
     // brightness settings
     digit_brightness_level: u8,
     car_brightness_level: u8,
     platform_brightness_level: u8,
     switch_brightness_level: u8,
     // gameplay settings
-    // game_speed: u8, // TODO: add game speed setting
+    game_speed_level: u8,
+    smooth_motion: bool, // trains glide between LEDs instead of stepping
+    train_style_level: u8,
+    station_dwell_level: u8, // trains stop at stations by themselves if not 0
     // other
     buzzer_enabled: bool,
 }
 
-impl GameSettings {
-    pub fn new(eeprom: Eeprom) -> Self {
-        let mut digit_brightness_level = eeprom.read_byte(0);
-        if digit_brightness_level > DIGITS_MAX_BRIGHTNESS {
-            digit_brightness_level = 1;
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            digit_brightness_level: 1,
+            car_brightness_level: LED_BRIGHTNESS_LEVELS - 1, // max brightness
+            platform_brightness_level: LED_BRIGHTNESS_LEVELS >> 1, // half brightness
+            switch_brightness_level: (LED_BRIGHTNESS_LEVELS >> 1) - 1, // one level below half brightness
+            game_speed_level: DEFAULT_GAME_SPEED_LEVEL,
+            smooth_motion: false,
+            train_style_level: 0,   // classic
+            station_dwell_level: 0, // off
+            buzzer_enabled: true,
         }
+    }
+}
 
-        let mut car_brightness_level = eeprom.read_byte(1);
-        if car_brightness_level >= LED_BRIGHTNESS_LEVELS {
-            car_brightness_level = LED_BRIGHTNESS_LEVELS - 1; // max brightness
+impl GameSettings {
+    /// Loads the settings record, migrating from the unversioned layout if there is none.
+    /// Defaults are used if the record is corrupt.
+    pub fn new<EEPROM>(eeprom: &EEPROM) -> Self
+    where
+        EEPROM: Storage,
+    {
+        match SETTINGS_RING.load(eeprom) {
+            // migrate older versions here, version 1 is the first versioned layout
+            Ok(record) if record.first() == Some(&SETTINGS_VERSION) => {
+                Self::from_payload(&record[1..])
+            }
+            Ok(_) | Err(RecordError::Corrupt) => Self::default(),
+            Err(RecordError::Missing) => Self::from_legacy(eeprom),
         }
+    }
 
-        let mut platform_brightness_level = eeprom.read_byte(2);
-        if platform_brightness_level >= LED_BRIGHTNESS_LEVELS {
-            platform_brightness_level = LED_BRIGHTNESS_LEVELS >> 1; // half brightness
-        }
+    /// Saves the settings record, nothing is written if the settings are unchanged.
+    pub fn save<EEPROM>(&self, eeprom: &mut EEPROM)
+    where
+        EEPROM: Storage,
+    {
+        let mut record = Vec::<u8, RECORD_SIZE>::new();
+        record.push(SETTINGS_VERSION).ok();
+        record.extend_from_slice(&self.to_payload()).ok();
+        SETTINGS_RING.save(eeprom, &record);
+    }
 
-        let mut switch_brightness_level = eeprom.read_byte(3);
-        if switch_brightness_level >= LED_BRIGHTNESS_LEVELS {
-            switch_brightness_level = (LED_BRIGHTNESS_LEVELS >> 1) - 1; // one level below half brightness
+    fn from_legacy<EEPROM>(eeprom: &EEPROM) -> Self
+    where
+        EEPROM: Storage,
+    {
+        let mut payload = Vec::<u8, LEGACY_SIZE>::new();
+        for i in 0..LEGACY_SIZE as u16 {
+            payload.push(eeprom.read_byte(LEGACY_OFFSET + i)).ok();
         }
-        
-        let buzzer_enabled = eeprom.read_byte(4) != 0;
+        Self::from_payload(&payload)
+    }
+
+    /// Reads settings from payload fields, missing or out of range fields use their default.
+    fn from_payload(payload: &[u8]) -> Self {
+        let defaults = Self::default();
+        let field = |index: usize, max: u8, default: u8| match payload.get(index) {
+            Some(&value) if value <= max => value,
+            _ => default,
+        };
 
         Self {
-            eeprom,
-            digit_brightness_level,
-            car_brightness_level,
-            platform_brightness_level,
-            switch_brightness_level,
-            buzzer_enabled,
+            digit_brightness_level: field(
+                DIGIT_BRIGHTNESS_FIELD,
+                DIGITS_MAX_BRIGHTNESS,
+                defaults.digit_brightness_level,
+            ),
+            car_brightness_level: field(
+                CAR_BRIGHTNESS_FIELD,
+                LED_BRIGHTNESS_LEVELS - 1,
+                defaults.car_brightness_level,
+            ),
+            platform_brightness_level: field(
+                PLATFORM_BRIGHTNESS_FIELD,
+                LED_BRIGHTNESS_LEVELS - 1,
+                defaults.platform_brightness_level,
+            ),
+            switch_brightness_level: field(
+                SWITCH_BRIGHTNESS_FIELD,
+                LED_BRIGHTNESS_LEVELS - 1,
+                defaults.switch_brightness_level,
+            ),
+            game_speed_level: field(
+                GAME_SPEED_FIELD,
+                GAME_SPEED_LEVELS - 1,
+                defaults.game_speed_level,
+            ),
+            train_style_level: field(
+                TRAIN_STYLE_FIELD,
+                NUM_TRAIN_STYLES - 1,
+                defaults.train_style_level,
+            ),
+            station_dwell_level: field(
+                STATION_DWELL_FIELD,
+                STATION_DWELL_LEVELS - 1,
+                defaults.station_dwell_level,
+            ),
+            smooth_motion: payload
+                .get(SMOOTH_MOTION_FIELD)
+                .map_or(defaults.smooth_motion, |&value| value != 0),
+            buzzer_enabled: payload
+                .get(BUZZER_ENABLED_FIELD)
+                .map_or(defaults.buzzer_enabled, |&value| value != 0),
         }
     }
 
-    pub fn save(&mut self) {
-        self.eeprom.write_byte(0, self.digit_brightness_level);
-        self.eeprom.write_byte(1, self.car_brightness_level);
-        self.eeprom.write_byte(2, self.platform_brightness_level);
-        self.eeprom.write_byte(3, self.switch_brightness_level);
-        self.eeprom.write_byte(4, self.buzzer_enabled as u8);
+    fn to_payload(&self) -> [u8; PAYLOAD_SIZE] {
+        let mut payload = [0; PAYLOAD_SIZE];
+        payload[DIGIT_BRIGHTNESS_FIELD] = self.digit_brightness_level;
+        payload[CAR_BRIGHTNESS_FIELD] = self.car_brightness_level;
+        payload[PLATFORM_BRIGHTNESS_FIELD] = self.platform_brightness_level;
+        payload[SWITCH_BRIGHTNESS_FIELD] = self.switch_brightness_level;
+        payload[BUZZER_ENABLED_FIELD] = self.buzzer_enabled as u8;
+        payload[GAME_SPEED_FIELD] = self.game_speed_level;
+        payload[SMOOTH_MOTION_FIELD] = self.smooth_motion as u8;
+        payload[TRAIN_STYLE_FIELD] = self.train_style_level;
+        payload[STATION_DWELL_FIELD] = self.station_dwell_level;
+        payload
     }
 
     #[inline(always)]
@@ -74,6 +189,16 @@ impl GameSettings {
         self.buzzer_enabled = !self.buzzer_enabled;
     }
 
+    #[inline(always)]
+    pub fn is_smooth_motion(&self) -> bool {
+        self.smooth_motion
+    }
+
+    #[inline(always)]
+    pub fn toggle_smooth_motion(&mut self) {
+        self.smooth_motion = !self.smooth_motion;
+    }
+
     #[inline(always)]
     pub fn digit_brightness_level(&self) -> u8 {
         self.digit_brightness_level
@@ -109,6 +234,92 @@ impl GameSettings {
         self.switch_brightness_level
     }
 
+    #[inline(always)]
+    pub fn game_speed_level(&self) -> u8 {
+        self.game_speed_level
+    }
+
+    /// Scales a train speed by the game speed
+    #[inline(always)]
+    pub fn scale_speed(&self, speed: u8) -> u8 {
+        (speed as u16 * GAME_SPEED_SCALES[self.game_speed_level as usize] as u16 / 100) as u8
+    }
+
+    /// Advances a fixed-point phase (high byte is the PWM phase) by phase_inc scaled by the game speed
+    #[inline(always)]
+    pub fn advance_phase(&self, phase: u16, phase_inc: u8) -> u16 {
+        let scaled_inc =
+            phase_inc as u32 * GAME_SPEED_SCALES[self.game_speed_level as usize] as u32 * 256 / 100;
+        phase.wrapping_add(scaled_inc as u16)
+    }
+
+    /// Returns the LED driver blink period, in 0.27s units, matching a blink pattern at the game speed,
+    /// None if the driver can't blink that slowly
+    #[inline(always)]
+    pub fn blink_period(&self) -> Option<u8> {
+        Some(BLINK_PERIODS[self.game_speed_level as usize]).filter(|&period| period > 0)
+    }
+
+    pub fn inc_game_speed_level(&mut self) {
+        if self.game_speed_level < GAME_SPEED_LEVELS - 1 {
+            self.game_speed_level += 1;
+        }
+    }
+
+    pub fn dec_game_speed_level(&mut self) {
+        if self.game_speed_level > 0 {
+            self.game_speed_level -= 1;
+        }
+    }
+
+    #[inline(always)]
+    pub fn train_style_level(&self) -> u8 {
+        self.train_style_level
+    }
+
+    #[inline(always)]
+    pub fn train_style(&self) -> TrainStyle {
+        TrainStyle::from_level(self.train_style_level)
+    }
+
+    pub fn inc_train_style_level(&mut self) {
+        if self.train_style_level < NUM_TRAIN_STYLES - 1 {
+            self.train_style_level += 1;
+        }
+    }
+
+    pub fn dec_train_style_level(&mut self) {
+        if self.train_style_level > 0 {
+            self.train_style_level -= 1;
+        }
+    }
+
+    #[inline(always)]
+    pub fn station_dwell_level(&self) -> u8 {
+        self.station_dwell_level
+    }
+
+    /// Returns the ticks trains wait at a station when they stop there by themselves, None if they don't
+    #[inline(always)]
+    pub fn station_dwell_ticks(&self) -> Option<u8> {
+        match self.station_dwell_level {
+            0 => None,
+            level => Some(level * STATION_DWELL_STEP),
+        }
+    }
+
+    pub fn inc_station_dwell_level(&mut self) {
+        if self.station_dwell_level < STATION_DWELL_LEVELS - 1 {
+            self.station_dwell_level += 1;
+        }
+    }
+
+    pub fn dec_station_dwell_level(&mut self) {
+        if self.station_dwell_level > 0 {
+            self.station_dwell_level -= 1;
+        }
+    }
+
     pub fn inc_digit_brightness_level(&mut self) {
         if self.digit_brightness_level < DIGITS_MAX_BRIGHTNESS {
             self.digit_brightness_level += 1;
@@ -2,18 +2,28 @@ use heapless::Vec;
 
 use crate::{
     cargo::*,
+    effect::Effect,
     game_settings::GameSettings,
-    location::{Direction, NUM_PLATFORMS, NUM_SWITCHES},
+    high_scores::HighScores,
+    location::{Direction, Location, NUM_PLATFORMS, NUM_SWITCHES},
+    modes::GameMode,
     platform::Platform,
     random::Rand,
+    route::Route,
     switch::Switch,
-    train::{Car, Train, DEFAULT_SPEED},
+    train::{TrainRef, TrainStyle, Trains, DEFAULT_SPEED},
     NUM_DIGITS,
 };
 
 pub const MAX_CARS: usize = 128;
 pub const MAX_TRAINS: usize = 8;
-pub const TRAIN_SIZE: usize = MAX_CARS / MAX_TRAINS;
+pub const TRAIN_SIZE: usize = MAX_CARS / MAX_TRAINS; // max cars of a train when sharing the pool evenly
+
+const AUTOPILOT_LOOKAHEAD: u8 = 32; // moves ahead the autopilot looks for cargo, bounds the search time
+const AUTOPILOT_CLEARANCE: u8 = 16; // moves ahead kept clear of other trains when there is no cargo in reach
+const AUTOPILOT_HEADWAY: usize = 6; // moves ahead of other engines treated as occupied
+const ADD_TRAIN_TRIES: u8 = 8; // random platforms tried for an unoccupied start
+const STATION_APPROACH: u8 = 6; // moves ahead a train starts braking for a station it stops at
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum DisplayState {
@@ -26,6 +36,7 @@ impl DisplayState {
     pub const DED: DisplayState = DisplayState::Text(*b"ded");
     pub const GG: DisplayState = DisplayState::Text(*b" gg");
     pub const OVR: DisplayState = DisplayState::Text(*b"ovr");
+    pub const HI: DisplayState = DisplayState::Text(*b" hi");
     pub const PAUSE_BYTES: [u8; NUM_DIGITS as usize] = [0u8, 0x36, 0u8];
     pub const PAUSE: DisplayState = DisplayState::Segments(Self::PAUSE_BYTES);
 }
@@ -36,31 +47,46 @@ pub struct GameState {
     pub is_over: bool,            // stops entity updates, game is over
     pub is_paused: bool,          // stops entity updates, game is still active
     pub redraw: bool,             // flag to redraw board LEDs
+    pub effect: Option<Effect>,   // board-wide LED effect drawn over the entities until done
+    pub train_style: Option<TrainStyle>, // style the mode draws trains in, the setting is used if None
     pub display: DisplayState,
     pub settings: GameSettings,
+    pub high_scores: HighScores,
 
     // game entities
-    pub cars: [Car; MAX_CARS],
-    pub trains: Vec<Train, MAX_TRAINS>,
+    pub trains: Trains,
     pub platforms: [Platform; NUM_PLATFORMS],
     pub switches: [Switch; NUM_SWITCHES],
 }
 
 impl GameState {
+    /// Returns the style trains are drawn in, the mode's style or the one from the settings
+    pub fn train_style(&self) -> TrainStyle {
+        self.train_style.unwrap_or(self.settings.train_style())
+    }
+
+    /// Records the final score of the current mode, returns true if it is a new best.
+    pub fn record_score(&mut self, score: u16) -> bool {
+        let name = GameMode::mode_name(self.target_mode_index);
+        self.high_scores.submit(&name, score)
+    }
+
     pub fn add_train(&mut self, cargo: Cargo, num_cars: u8, max_cars: u8, speed: Option<u8>) {
-        if self.trains.is_full() {
-            return;
+        // start at a platform no other train is at, giving up after a few tries on a crowded board
+        let mut loc = self.rand_platform().track_location();
+        for _ in 0..ADD_TRAIN_TRIES {
+            if !self.trains.at_location(loc) {
+                break;
+            }
+            loc = self.rand_platform().track_location();
         }
-
-        // TODO: for now, simple allocation method that divides evenly on MAX_TRAINS, only snake allocated single train with max cars
-        let cars_ptr = unsafe { self.cars.as_mut_ptr().add(self.trains.len() * TRAIN_SIZE) };
-        let loc = self.rand_platform().track_location();
-        let mut train = Train::new(cars_ptr, max_cars, loc, cargo, speed);
-        for _ in 1..num_cars {
-            train.add_car(cargo);
+        if let Some(index) = self.trains.push(loc, cargo, max_cars, speed) {
+            let mut train = self.trains.get_mut(index).unwrap();
+            for _ in 1..num_cars {
+                train.add_car(cargo);
+            }
+            self.redraw = true;
         }
-        self.trains.push(train).ok();
-        self.redraw = true;
     }
 
     pub fn remove_train(&mut self) {
@@ -74,12 +100,10 @@ impl GameState {
     pub fn init_trains(&mut self, cargo: Cargo, num_cars: u8, max_cars: u8) {
         // init first train
         if self.trains.len() > 0 {
-            while self.trains.len() > 1 {
-                self.trains.pop();
-            }
+            self.trains.truncate(1);
 
             // reuse existing train for smooth transition between modes
-            let train = &mut self.trains[0];
+            let mut train = self.trains.get_mut(0).unwrap();
             train.init_cars(cargo, num_cars, max_cars);
             train.set_speed(DEFAULT_SPEED);
             self.redraw = true;
@@ -108,9 +132,132 @@ impl GameState {
         &self.platforms[rand_platform_index]
     }
 
+    /// Throws switches ahead of the train to steer it to the nearest platform with cargo, avoiding other
+    /// trains, or just away from other trains if no cargo can be reached safely. Switches under other
+    /// trains are left alone. Returns the route the train was steered on, or None if every way ahead is
+    /// blocked.
+    pub fn autopilot(&mut self, train_index: usize) -> Option<Route> {
+        let train = self.trains.get(train_index)?;
+        let (front, direction) = (train.front(), train.direction());
+
+        // other trains keep moving, so the locations just ahead of their engines are avoided too
+        let mut headway: Vec<Location, { MAX_TRAINS * AUTOPILOT_HEADWAY }> = Vec::new();
+        for (other_index, other_train) in self.trains.iter().enumerate() {
+            if other_index == train_index {
+                continue;
+            }
+            let (mut loc, mut dir) = (other_train.front(), other_train.direction());
+            for _ in 0..AUTOPILOT_HEADWAY {
+                (loc, dir) = self.next_location(loc, dir);
+                headway.push(loc).ok();
+            }
+        }
+
+        let trains = &self.trains;
+        let platforms = &self.platforms;
+        let is_blocked = |loc| {
+            let occupied = trains
+                .train_at(loc)
+                .is_some_and(|owner| owner != train_index);
+            occupied || headway.contains(&loc)
+        };
+        let has_cargo = |loc| {
+            platforms
+                .iter()
+                .any(|platform| !platform.is_empty() && platform.track_location() == loc)
+        };
+
+        // the way on from the cargo must be clear too, or the train is steered into a trap
+        let cargo_route = Route::find_nearest_clear(
+            front,
+            direction,
+            AUTOPILOT_LOOKAHEAD,
+            has_cargo,
+            &is_blocked,
+        );
+        let onward_route = cargo_route.and_then(|route| {
+            Route::find_clear(
+                route.end(),
+                route.end_direction(),
+                AUTOPILOT_CLEARANCE,
+                &is_blocked,
+            )
+        });
+        let routes = match (cargo_route, onward_route) {
+            (Some(route), Some(onward)) => [Some(route), Some(onward)],
+            _ => [
+                Route::find_clear(front, direction, AUTOPILOT_CLEARANCE, &is_blocked),
+                None,
+            ],
+        };
+        let route = routes[0]?;
+
+        for (leg_index, leg) in routes.iter().flatten().enumerate() {
+            for (switch_index, switch_direction, switched) in leg.required_states() {
+                // the route to the cargo comes first, the onward route can't throw its switches back
+                let cargo_state = route.required_state(switch_index, switch_direction);
+                if leg_index > 0 && cargo_state.is_some() {
+                    continue;
+                }
+                let switch = &mut self.switches[switch_index];
+                if !is_blocked(switch.location()) {
+                    switch.set_switched(switch_direction, switched);
+                }
+            }
+        }
+        Some(route)
+    }
+
+    /// Stops the train at platforms stops_at picks, e.g. ones it can load or unload cargo at, and keeps it
+    /// there for dwell_ticks. The train brakes when such a platform is close ahead and departs at its
+    /// previous speed after the dwell. Call on each train advance, before the cargo of the platforms changes.
+    pub fn auto_stop<F>(&mut self, train_index: usize, dwell_ticks: u8, stops_at: F)
+    where
+        F: Fn(TrainRef, &Platform) -> bool,
+    {
+        let Some(train) = self.trains.get(train_index) else {
+            return;
+        };
+        if train.is_dwelling() {
+            return;
+        }
+
+        let is_station = |loc| {
+            self.platforms
+                .iter()
+                .any(|platform| platform.track_location() == loc && stops_at(train, platform))
+        };
+        let (mut loc, mut dir) = (train.front(), train.direction());
+        let at_station = is_station(loc);
+        let station_ahead = !at_station
+            && (0..STATION_APPROACH).any(|_| {
+                (loc, dir) = self.next_location(loc, dir);
+                is_station(loc)
+            });
+
+        let mut train = self.trains.get_mut(train_index).unwrap();
+        if at_station {
+            train.stop_at_station(dwell_ticks);
+        } else if station_ahead {
+            train.approach_station();
+        } else {
+            // the route changed or the cargo is gone, carry on
+            train.depart();
+        }
+    }
+
+    /// Returns the location and direction an engine moves to from loc with the switches as they are
+    fn next_location(&self, loc: Location, dir: Direction) -> (Location, Direction) {
+        let is_switched = self
+            .switches
+            .iter()
+            .any(|switch| switch.location() == loc && switch.is_switched(dir));
+        loc.next(dir, is_switched)
+    }
+
     /// If the train just left a switch, switch it.
     pub fn train_switch(&mut self, train_index: usize) {
-        let train = &self.trains[train_index];
+        let train = self.trains.get(train_index).unwrap();
         let caboose_loc = train.caboose().loc;
         let last_loc = train.last_loc();
 
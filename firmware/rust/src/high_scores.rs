@@ -0,0 +1,135 @@
+use crate::{board::Storage, record_ring::RecordRing, NUM_DIGITS};
+
+const MAX_SCORED_MODES: usize = 4; // number of modes that can have records
+pub const SCORES_PER_MODE: usize = 3; // best scores kept per mode
+
+const NAME_SIZE: usize = NUM_DIGITS as usize;
+const ENTRY_SIZE: usize = NAME_SIZE + SCORES_PER_MODE * 2;
+const TABLE_SIZE: usize = ENTRY_SIZE * MAX_SCORED_MODES;
+const HIGH_SCORES_SLOTS: u8 = 12;
+// after the settings, ends before the end of the 1KB EEPROM
+const HIGH_SCORES_RING: RecordRing<TABLE_SIZE> = RecordRing::new(0x200, HIGH_SCORES_SLOTS);
+const _: () = assert!(HIGH_SCORES_RING.end() <= 0x400);
+
+type ModeName = [u8; NAME_SIZE];
+
+/// Best scores of a mode, highest first, keyed by the mode's display name so records stay with their
+/// mode when modes are added or removed.
+#[derive(Clone, Copy)]
+struct ScoreEntry {
+    name: ModeName,
+    scores: [u16; SCORES_PER_MODE],
+}
+
+impl ScoreEntry {
+    // names are always printable ASCII
+    const EMPTY_NAME: ModeName = [0xFF; NAME_SIZE];
+
+    const EMPTY: ScoreEntry = ScoreEntry {
+        name: Self::EMPTY_NAME,
+        scores: [0; SCORES_PER_MODE],
+    };
+
+    fn is_empty(&self) -> bool {
+        self.name == Self::EMPTY_NAME
+    }
+}
+
+pub struct HighScores {
+    entries: [ScoreEntry; MAX_SCORED_MODES],
+    dirty: bool,
+}
+
+impl HighScores {
+    /// Loads the table, starting with no records if it was never saved or is corrupt.
+    pub fn new<EEPROM>(eeprom: &EEPROM) -> Self
+    where
+        EEPROM: Storage,
+    {
+        let mut entries = [ScoreEntry::EMPTY; MAX_SCORED_MODES];
+        if let Ok(table) = HIGH_SCORES_RING.load(eeprom) {
+            for (entry, bytes) in entries.iter_mut().zip(table.chunks_exact(ENTRY_SIZE)) {
+                let (name, scores) = bytes.split_at(NAME_SIZE);
+                entry.name.copy_from_slice(name);
+                for (score, score_bytes) in entry.scores.iter_mut().zip(scores.chunks_exact(2)) {
+                    *score = u16::from_le_bytes([score_bytes[0], score_bytes[1]]);
+                }
+            }
+        }
+
+        Self {
+            entries,
+            dirty: false,
+        }
+    }
+
+    /// Saves the table if it changed, each save rotates to the next slot of the ring.
+    pub fn save<EEPROM>(&mut self, eeprom: &mut EEPROM)
+    where
+        EEPROM: Storage,
+    {
+        let mut table = [0; TABLE_SIZE];
+        for (entry, bytes) in self.entries.iter().zip(table.chunks_exact_mut(ENTRY_SIZE)) {
+            let (name, scores) = bytes.split_at_mut(NAME_SIZE);
+            name.copy_from_slice(&entry.name);
+            for (score, score_bytes) in entry.scores.iter().zip(scores.chunks_exact_mut(2)) {
+                score_bytes.copy_from_slice(&score.to_le_bytes());
+            }
+        }
+        HIGH_SCORES_RING.save(eeprom, &table);
+        self.dirty = false;
+    }
+
+    #[inline(always)]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the best scores of the mode, highest first, or None if the mode has no records.
+    pub fn scores(&self, name: &ModeName) -> Option<&[u16; SCORES_PER_MODE]> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == *name)
+            .map(|entry| &entry.scores)
+    }
+
+    pub fn best(&self, name: &ModeName) -> Option<u16> {
+        self.scores(name)
+            .map(|scores| scores[0])
+            .filter(|&score| score > 0)
+    }
+
+    /// Adds the score to the mode's records if it makes the table, returns true if it is a new best.
+    pub fn submit(&mut self, name: &ModeName, score: u16) -> bool {
+        if score == 0 {
+            return false;
+        }
+
+        let entry_index = match self.entries.iter().position(|entry| entry.name == *name) {
+            Some(entry_index) => entry_index,
+            None => match self.entries.iter().position(ScoreEntry::is_empty) {
+                Some(entry_index) => {
+                    self.entries[entry_index] = ScoreEntry {
+                        name: *name,
+                        scores: [0; SCORES_PER_MODE],
+                    };
+                    entry_index
+                }
+                None => return false, // no room for another mode
+            },
+        };
+
+        let scores = &mut self.entries[entry_index].scores;
+        let rank = match scores.iter().position(|&s| score > s) {
+            Some(rank) => rank,
+            None => return false,
+        };
+        for i in (rank + 1..SCORES_PER_MODE).rev() {
+            scores[i] = scores[i - 1];
+        }
+        scores[rank] = score;
+        self.dirty = true;
+
+        rank == 0
+    }
+}
@@ -1,16 +1,27 @@
-
 #![allow(unused)]
-use atmega_hal::port::{
-    mode::{Input, PullUp},
-    Dynamic, Pin,
-};
-use embedded_hal::digital::InputPin;
-use crate::NUM_BUTTONS;
+use heapless::Vec;
+
+use crate::{board::ButtonSource, NUM_BUTTONS};
+
+// gesture timing in game ticks
+pub const PRESS_CYCLES: u8 = 3; // held cycles for a press of a chord button, other buttons going down make a chord
+pub const HOLD_CYCLES: u8 = 50; // held cycles for a hold
+pub const REPEAT_CYCLES: u8 = 10; // cycles between repeats while held, after the hold
+pub const DOUBLE_TAP_CYCLES: u8 = 25; // max cycles from a release to the next press for a double tap
+pub const DEBOUNCE_CYCLES: u8 = 3; // cycles presses are ignored after a release
+const MAX_EVENTS: usize = 3 * NUM_BUTTONS + 1; // press, double tap and release for every button, plus a chord
+
+/// Bitmask of buttons pressed together, bit n is button index n
+pub type Chord = u16;
+pub const CHORD_UP_DOWN: Chord = (1 << 8) | (1 << 9);
+pub const CHORD_LEFT_RIGHT: Chord = (1 << 10) | (1 << 11);
+const CHORDS: [Chord; 2] = [CHORD_UP_DOWN, CHORD_LEFT_RIGHT];
+const CHORD_BUTTONS: Chord = CHORD_UP_DOWN | CHORD_LEFT_RIGHT; // presses held back in case a chord follows
+const DIRECTION_BUTTONS: Chord = 0xF << 8;
 
-const PRESS_CYCLES: u8 = 1;
-const HOLD_CYCLES: u8 = 50;
-const DEBOUNCE_CYCLES: u8 = 3;
+pub type InputEvents = Vec<InputEvent, MAX_EVENTS>;
 
+#[derive(Clone, Copy)]
 pub enum InputDirection {
     Up,
     Down,
@@ -20,93 +31,186 @@ pub enum InputDirection {
 
 pub enum InputEvent {
     SwitchButtonPressed(u8),
+    SwitchButtonDoubleTapped(u8), // follows the second press
     SwitchButtonHeld(u8),
+    SwitchButtonRepeated(u8), // every REPEAT_CYCLES after the hold
     SwitchButtonReleased(u8),
+    SwitchButtonHeldReleased(u8), // released after a hold
     DirectionButtonPressed(InputDirection),
+    DirectionButtonDoubleTapped(InputDirection),
     DirectionButtonHeld(InputDirection),
+    DirectionButtonRepeated(InputDirection),
     DirectionButtonReleased(InputDirection),
+    DirectionButtonHeldReleased(InputDirection),
+    ChordPressed(Chord), // a registered chord or direction buttons held together, has all held buttons
 }
 
-pub struct BoardInput {
-    button_pins: [Pin<Input<PullUp>, Dynamic>; NUM_BUTTONS],
+pub struct BoardInput<BUTTONS>
+where
+    BUTTONS: ButtonSource,
+{
+    buttons: BUTTONS,
     held_cycles: [u8; NUM_BUTTONS],
+    repeat_cycles: [u8; NUM_BUTTONS],
+    tap_cycles: [u8; NUM_BUTTONS], // cycles since a tap was released, MAX if no double tap can follow
     debounce_cycles: [u8; NUM_BUTTONS],
+    chorded: Chord, // buttons in a chord, they send no events of their own until released
 }
 
-impl BoardInput {
-    pub fn new(button_pins: [Pin<Input<PullUp>, Dynamic>; 12]) -> Self {
+impl<BUTTONS> BoardInput<BUTTONS>
+where
+    BUTTONS: ButtonSource,
+{
+    pub fn new(buttons: BUTTONS) -> Self {
         BoardInput {
-            button_pins,
+            buttons,
             held_cycles: [0; NUM_BUTTONS],
+            repeat_cycles: [0; NUM_BUTTONS],
+            tap_cycles: [u8::MAX; NUM_BUTTONS],
             debounce_cycles: [0; NUM_BUTTONS],
+            chorded: 0,
         }
     }
 
-    fn index_to_hold_event(index: usize) -> InputEvent {
-        match index {
-            0..=7 => InputEvent::SwitchButtonHeld(index as u8),
-            8 => InputEvent::DirectionButtonHeld(InputDirection::Up),
-            9 => InputEvent::DirectionButtonHeld(InputDirection::Down),
-            10 => InputEvent::DirectionButtonHeld(InputDirection::Left),
-            11 => InputEvent::DirectionButtonHeld(InputDirection::Right),
-            _ => unreachable!(),
-        }
+    pub fn buttons_mut(&mut self) -> &mut BUTTONS {
+        &mut self.buttons
     }
 
-    fn index_to_press_event(index: usize) -> InputEvent {
+    /// Maps a button index to its event, switch buttons are 0-7 and direction buttons are 8-11
+    fn index_to_event(
+        index: usize,
+        switch_event: fn(u8) -> InputEvent,
+        direction_event: fn(InputDirection) -> InputEvent,
+    ) -> InputEvent {
         match index {
-            0..=7 => InputEvent::SwitchButtonPressed(index as u8),
-            8 => InputEvent::DirectionButtonPressed(InputDirection::Up),
-            9 => InputEvent::DirectionButtonPressed(InputDirection::Down),
-            10 => InputEvent::DirectionButtonPressed(InputDirection::Left),
-            11 => InputEvent::DirectionButtonPressed(InputDirection::Right),
+            0..=7 => switch_event(index as u8),
+            8 => direction_event(InputDirection::Up),
+            9 => direction_event(InputDirection::Down),
+            10 => direction_event(InputDirection::Left),
+            11 => direction_event(InputDirection::Right),
             _ => unreachable!(),
         }
     }
 
-    fn index_to_release_event(index: usize) -> InputEvent {
-        match index {
-            0..=7 => InputEvent::SwitchButtonReleased(index as u8),
-            8 => InputEvent::DirectionButtonReleased(InputDirection::Up),
-            9 => InputEvent::DirectionButtonReleased(InputDirection::Down),
-            10 => InputEvent::DirectionButtonReleased(InputDirection::Left),
-            11 => InputEvent::DirectionButtonReleased(InputDirection::Right),
-            _ => unreachable!(),
+    /// Returns true if the buttons held together make a chord, a registered one or only direction buttons
+    fn is_chord(buttons: Chord) -> bool {
+        buttons.count_ones() > 1 && (CHORDS.contains(&buttons) || buttons & !DIRECTION_BUTTONS == 0)
+    }
+
+    /// Returns the held cycles for a press, buttons that can start a chord wait for the other button
+    fn press_cycles(index: usize) -> u8 {
+        if CHORD_BUTTONS & (1 << index) != 0 {
+            PRESS_CYCLES
+        } else {
+            1
         }
     }
 
-    pub fn update(&mut self) -> Option<InputEvent> {
-        // TODO: support multiple events?
-        for (i, button_pin) in self.button_pins.iter_mut().enumerate() {
-            let mut pressed = button_pin.is_low().unwrap();
+    /// Returns the events for all buttons that changed this tick.
+    pub fn update(&mut self) -> InputEvents {
+        use InputEvent::*;
+
+        let mut events = InputEvents::new();
+        let mut pressed = [false; NUM_BUTTONS];
+        let mut held_buttons: Chord = 0;
+        let mut new_buttons: Chord = 0;
 
+        // scan all buttons first, so buttons going down in the same scan are seen together
+        for (i, pressed) in pressed.iter_mut().enumerate() {
+            *pressed = self.buttons.is_pressed(i);
             if self.debounce_cycles[i] > 0 {
                 self.debounce_cycles[i] -= 1;
-                pressed = false;
+                *pressed = false;
             }
+            if *pressed {
+                held_buttons |= 1 << i;
+                if self.held_cycles[i] == 0 {
+                    new_buttons |= 1 << i;
+                }
+            }
+        }
+
+        // a button going down with other buttons held can make a chord, presses not sent yet are held back
+        // for good, other buttons keep their own events
+        if new_buttons != 0 && Self::is_chord(held_buttons) {
+            self.chorded |= held_buttons;
+            events.push(ChordPressed(held_buttons)).ok();
+        }
+
+        for (i, &pressed) in pressed.iter().enumerate() {
+            let is_chorded = self.chorded & (1 << i) != 0;
+            self.tap_cycles[i] = self.tap_cycles[i].saturating_add(1);
 
             if pressed {
-                self.held_cycles[i] += 1;
-                if self.held_cycles[i] == PRESS_CYCLES {
-                    return Some(Self::index_to_press_event(i))
+                self.held_cycles[i] = self.held_cycles[i].saturating_add(1);
+                if is_chorded {
+                    continue;
+                }
+
+                if self.held_cycles[i] == Self::press_cycles(i) {
+                    self.press(i, &mut events);
                 } else if self.held_cycles[i] == HOLD_CYCLES {
-                    return Some(Self::index_to_hold_event(i))
+                    self.repeat_cycles[i] = 0;
+                    let event = Self::index_to_event(i, SwitchButtonHeld, DirectionButtonHeld);
+                    events.push(event).ok();
+                } else if self.held_cycles[i] > HOLD_CYCLES {
+                    self.repeat_cycles[i] += 1;
+                    if self.repeat_cycles[i] == REPEAT_CYCLES {
+                        self.repeat_cycles[i] = 0;
+                        let event =
+                            Self::index_to_event(i, SwitchButtonRepeated, DirectionButtonRepeated);
+                        events.push(event).ok();
+                    }
                 }
             } else if self.held_cycles[i] > 0 {
+                let was_held = self.held_cycles[i] >= HOLD_CYCLES;
+                if !is_chorded {
+                    // a tap shorter than the press cycles still presses the button
+                    if self.held_cycles[i] < Self::press_cycles(i) {
+                        self.press(i, &mut events);
+                    }
+
+                    let event = if was_held {
+                        Self::index_to_event(
+                            i,
+                            SwitchButtonHeldReleased,
+                            DirectionButtonHeldReleased,
+                        )
+                    } else {
+                        Self::index_to_event(i, SwitchButtonReleased, DirectionButtonReleased)
+                    };
+                    events.push(event).ok();
+                }
+
                 self.debounce_cycles[i] = DEBOUNCE_CYCLES;
                 self.held_cycles[i] = 0;
-                return Some(Self::index_to_release_event(i))
-                
-                // only one release event type for now
-                //let was_held = self.held_cycles[i] >= HOLD_CYCLES;
-                // if was_held {
-                //     return Some(Self::index_to_holdrelease_event(i))
-                // } else {
-                //     return Some(Self::index_to_release_event(i))
-                // }
+                self.chorded &= !(1 << i);
+                // only a tap that wasn't held or chorded, or the second of a double tap, can start a double tap
+                if was_held || is_chorded || self.tap_cycles[i] == u8::MAX {
+                    self.tap_cycles[i] = u8::MAX;
+                } else {
+                    self.tap_cycles[i] = 0;
+                }
             }
         }
 
-        None
+        events
+    }
+
+    /// Sends the press of a button, and a double tap if it closely follows a tap of the same button
+    fn press(&mut self, i: usize, events: &mut InputEvents) {
+        use InputEvent::*;
+
+        let event = Self::index_to_event(i, SwitchButtonPressed, DirectionButtonPressed);
+        events.push(event).ok();
+
+        if self.tap_cycles[i] <= DOUBLE_TAP_CYCLES {
+            self.tap_cycles[i] = u8::MAX; // a third tap starts over
+            let event =
+                Self::index_to_event(i, SwitchButtonDoubleTapped, DirectionButtonDoubleTapped);
+            events.push(event).ok();
+        } else {
+            self.tap_cycles[i] = 0;
+        }
     }
 }
@@ -0,0 +1,59 @@
+#![no_std]
+
+// TODO: can print to digits before panic halting
+#[cfg(not(feature = "panic_to_digits"))]
+#[macro_export]
+macro_rules! panic_with_error {
+    ($error_code:expr) => {
+        panic!()
+    };
+}
+
+#[cfg(feature = "panic_to_digits")]
+#[macro_export]
+macro_rules! panic_with_error {
+    ($error_code:expr) => {{
+        $crate::set_error_code($error_code);
+        panic!()
+    }};
+}
+
+pub mod board;
+pub mod cargo;
+pub mod clock;
+pub mod effect;
+pub mod frame_buffer;
+pub mod game;
+pub mod game_settings;
+pub mod game_state;
+pub mod high_scores;
+pub mod input;
+pub mod location;
+pub mod modes;
+pub mod occupancy;
+pub mod platform;
+pub mod random;
+pub mod record_ring;
+pub mod route;
+#[cfg(feature = "std")]
+pub mod sim;
+pub mod switch;
+pub mod train;
+
+pub const NUM_BUTTONS: usize = 12;
+pub const NUM_DIGITS: u8 = 3;
+
+#[cfg(feature = "panic_to_digits")]
+static mut ERROR_CODE: u16 = 0;
+
+#[cfg(feature = "panic_to_digits")]
+pub fn set_error_code(error_code: u16) {
+    unsafe {
+        ERROR_CODE = error_code;
+    }
+}
+
+#[cfg(feature = "panic_to_digits")]
+pub fn error_code() -> u16 {
+    unsafe { ERROR_CODE }
+}
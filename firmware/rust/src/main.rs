@@ -5,19 +5,13 @@
 #![feature(panic_info_message)]
 #![feature(type_alias_impl_trait)]
 
-// TODO: can print to digits before panic halting
-#[cfg(not(feature = "panic_to_digits"))]
-#[macro_export]
-macro_rules! panic_with_error {
-    ($error_code:expr) => {
-        panic!()
-    };
-}
-
 use atmega_hal::adc;
 use core::cell::RefCell;
-use embedded_hal::delay::DelayNs;
 use embedded_hal_bus::i2c::{self};
+use light_rail::{
+    board::{self, LedSink},
+    clock, game, game_settings, input, random,
+};
 #[cfg(not(feature = "panic_to_digits"))]
 use panic_halt as _;
 
@@ -30,25 +24,12 @@ type Delay = atmega_hal::delay::Delay<CoreClock>;
 type Eeprom = atmega_hal::eeprom::Eeprom;
 type I2c = atmega_hal::i2c::I2c<CoreClock>;
 
-mod cargo;
-mod game;
-mod game_settings;
-mod game_state;
-mod input;
-mod location;
-mod modes;
+mod millis;
 #[cfg(feature = "panic_to_digits")]
 mod panic;
-mod platform;
-mod random;
-mod switch;
 #[cfg_attr(not(feature = "atmega32u4"), path = "notone.rs")]
 mod tone;
-mod train;
 
-const BASE_DELAY: u32 = 10;
-const NUM_BUTTONS: usize = 12;
-const NUM_DIGITS: u8 = 3;
 const DIGITS_I2C_ADDR: u8 = as1115::DEFAULT_ADDRESS;
 const LEDS_I2C_ADDR: u8 = is31fl3731::DEFAULT_ADDRESS;
 
@@ -76,8 +57,8 @@ fn main() -> ! {
     #[cfg(feature = "atmega328p")]
     let board_buzzer = tone::TimerTone::new();
 
-    let eeprom = Eeprom::new(dp.EEPROM);
-    let settings = game_settings::GameSettings::new(eeprom);
+    let board_eeprom = Eeprom::new(dp.EEPROM);
+    let settings = game_settings::GameSettings::new(&board_eeprom);
 
     let mut board_digits =
         as1115::AS1115::new(i2c::RefCellDevice::new(&i2c_ref_cell), DIGITS_I2C_ADDR);
@@ -116,10 +97,15 @@ fn main() -> ! {
     ];
     let board_input = input::BoardInput::new(input_pins);
 
-    let mut board_leds =
+    let mut leds_driver =
         is31fl3731::IS31FL3731::new(i2c::RefCellDevice::new(&i2c_ref_cell), LEDS_I2C_ADDR);
-    board_leds.setup_blocking(&mut delay).unwrap(); // TODO: why does OK hang???
-    board_leds.clear_blocking().ok();
+    leds_driver.setup_blocking(&mut delay).unwrap(); // TODO: why does OK hang???
+    leds_driver.clear_blocking().ok();
+
+    // game draws by page flipping between two frames, in bursts of changed pixels
+    let mut board_leds: board::PagedLeds<_, true> =
+        board::PagedLeds::new(i2c::RefCellDevice::new(&i2c_ref_cell), LEDS_I2C_ADDR);
+    board_leds.clear();
 
     // generate random seed from ADC temperature sensor
     let mut adc = Adc::new(dp.ADC, Default::default());
@@ -132,19 +118,22 @@ fn main() -> ! {
     }
     random::Rand::seed(seed);
 
-    let cars = [train::Car::default(); game_state::MAX_CARS];
     let mut game = game::Game::new(
         board_buzzer,
         board_digits,
         board_input,
         board_leds,
-        cars,
+        board_eeprom,
         settings,
     );
     game.restart();
 
+    // game ticks at a fixed rate from the millisecond timer, however long I2C updates take
+    millis::init(dp.TC0);
+    let mut scheduler = clock::Scheduler::new(clock::TICK_MS);
     loop {
-        game.tick();
-        delay.delay_ms(BASE_DELAY);
+        for _ in 0..scheduler.due() {
+            game.tick();
+        }
     }
 }
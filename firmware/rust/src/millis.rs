@@ -0,0 +1,29 @@
+use atmega_hal::clock::Clock;
+
+type Timer = atmega_hal::pac::TC0;
+
+// 1 ms compare match, 125 counts at 8MHz and 250 counts at 16MHz
+const PRESCALAR: u32 = 64;
+const TIMER_COUNTS: u32 = crate::CoreClock::FREQ / PRESCALAR / 1000;
+
+const _: () = assert!(TIMER_COUNTS <= 256, "millisecond timer counts must fit in 8 bits");
+
+/// Starts Timer0 interrupting every millisecond to advance the game clock, see light_rail::clock
+pub fn init(timer: Timer) {
+    // set timer for CTC mode, WGM0 = 0b010
+    timer.tccr0a.write(|w| w.wgm0().ctc());
+    timer.ocr0a.write(|w| w.bits((TIMER_COUNTS - 1) as u8));
+    timer.tccr0b.write(|w| w.cs0().prescale_64());
+    timer.timsk0.write(|w| w.ocie0a().set_bit());
+
+    unsafe {
+        avr_device::interrupt::enable();
+    }
+}
+
+#[cfg_attr(feature = "atmega32u4", avr_device::interrupt(atmega32u4))]
+#[cfg_attr(feature = "atmega328p", avr_device::interrupt(atmega328p))]
+#[allow(non_snake_case)]
+fn TIMER0_COMPA() {
+    light_rail::clock::advance(1);
+}
@@ -14,6 +14,7 @@ use crate::{
 enum Setting {
     Score,
     RandomSwitching,
+    Autopilot,
     Trains,
     TrainCars,
     TrainSpeed,
@@ -24,6 +25,7 @@ pub struct FreeplayMode {
     cur_setting: Setting,
     cur_train_index: u8,
     random_switching: bool,
+    autopilot: bool,
 }
 
 impl FreeplayMode {
@@ -40,6 +42,13 @@ impl FreeplayMode {
                 segments[2] = ascii_to_segment(if self.random_switching { b'1' } else { b'0' });
                 DisplayState::Segments(segments)
             }
+            Setting::Autopilot => {
+                let mut segments = [b' '; NUM_DIGITS as usize];
+                segments[0] = ascii_to_segment(b'A');
+                segments[1] = ascii_to_segment(b'P') | as1115::segments::DP;
+                segments[2] = ascii_to_segment(if self.autopilot { b'1' } else { b'0' });
+                DisplayState::Segments(segments)
+            }
             Setting::Trains => {
                 let num_trains = state.trains.len() as u8;
                 let mut segments = [b' '; NUM_DIGITS as usize];
@@ -74,6 +83,9 @@ impl FreeplayMode {
                     self.cur_setting = Setting::RandomSwitching;
                 }
                 Setting::RandomSwitching => {
+                    self.cur_setting = Setting::Autopilot;
+                }
+                Setting::Autopilot => {
                     self.cur_setting = Setting::Trains;
                 }
                 Setting::Trains => {
@@ -103,9 +115,12 @@ impl FreeplayMode {
                 Setting::RandomSwitching => {
                     self.cur_setting = Setting::Score;
                 }
-                Setting::Trains => {
+                Setting::Autopilot => {
                     self.cur_setting = Setting::RandomSwitching;
                 }
+                Setting::Trains => {
+                    self.cur_setting = Setting::Autopilot;
+                }
                 Setting::TrainCars => {
                     if self.cur_train_index == 0 {
                         self.cur_setting = Setting::Trains;
@@ -132,15 +147,21 @@ impl FreeplayMode {
             Setting::RandomSwitching => {
                 self.random_switching = !self.random_switching;
             }
+            Setting::Autopilot => {
+                self.autopilot = !self.autopilot;
+            }
             Setting::Trains => {
                 if inc {
                     state.add_train(Cargo::Full(LedPattern::Solid), 3, TRAIN_SIZE as u8, None);
                 } else {
                     state.remove_train();
                 }
+                // keep the selection on a train that still exists
+                let last_index = state.trains.len() as u8 - 1;
+                self.cur_train_index = self.cur_train_index.min(last_index);
             }
             Setting::TrainCars => {
-                if let Some(train) = state.trains.get_mut(self.cur_train_index as usize) {
+                if let Some(mut train) = state.trains.get_mut(self.cur_train_index as usize) {
                     if inc {
                         train.add_car(Cargo::Full(LedPattern::Solid));
                     } else {
@@ -150,11 +171,11 @@ impl FreeplayMode {
                 }
             }
             Setting::TrainSpeed => {
-                if let Some(train) = state.trains.get_mut(self.cur_train_index as usize) {
+                if let Some(mut train) = state.trains.get_mut(self.cur_train_index as usize) {
                     let speed = train.speed();
                     if inc {
                         if speed < Self::MAX_SPEED {
-                            train.set_speed(speed + Self::SPEED_INC);
+                            train.set_speed((speed + Self::SPEED_INC).min(Self::MAX_SPEED));
                         }
                     } else {
                         if speed > Self::SPEED_INC {
@@ -179,6 +200,7 @@ impl Default for FreeplayMode {
             cur_setting: Setting::Score,
             cur_train_index: 0,
             random_switching: true,
+            autopilot: false,
         }
     }
 }
@@ -186,6 +208,9 @@ impl Default for FreeplayMode {
 impl GameModeHandler for FreeplayMode {
     fn on_restart(&mut self, state: &mut GameState) {
         self.score = 0;
+        // restarting drops back to one train, so the selection starts over
+        self.cur_setting = Setting::Score;
+        self.cur_train_index = 0;
         state.display = DisplayState::Score(self.score);
         state.is_over = false;
 
@@ -209,10 +234,12 @@ impl GameModeHandler for FreeplayMode {
             InputEvent::DirectionButtonPressed(InputDirection::Down) => {
                 self.next_setting(state, true)
             }
-            InputEvent::DirectionButtonPressed(InputDirection::Left) => {
+            InputEvent::DirectionButtonPressed(InputDirection::Left)
+            | InputEvent::DirectionButtonRepeated(InputDirection::Left) => {
                 self.update_setting(state, false)
             }
-            InputEvent::DirectionButtonPressed(InputDirection::Right) => {
+            InputEvent::DirectionButtonPressed(InputDirection::Right)
+            | InputEvent::DirectionButtonRepeated(InputDirection::Right) => {
                 self.update_setting(state, true)
             }
             _ => {}
@@ -220,12 +247,19 @@ impl GameModeHandler for FreeplayMode {
     }
 
     fn on_train_advance(&mut self, train_index: usize, state: &mut GameState) {
-        if self.random_switching && Rand::default().get_bool() {
+        // autopilot steers the trains to cargo, taking over from random switching
+        if self.autopilot {
+            state.autopilot(train_index);
+        } else if self.random_switching && Rand::default().get_bool() {
             state.train_switch(train_index);
         }
+        // with station stops on, trains wait at the platforms they collect cargo from
+        if let Some(dwell_ticks) = state.settings.station_dwell_ticks() {
+            state.auto_stop(train_index, dwell_ticks, |_, platform| !platform.is_empty());
+        }
 
         // Clear cargo if train front is at a platform with cargo
-        let train = &state.trains[train_index];
+        let train = state.trains.get(train_index).unwrap();
         for platform in state.platforms.iter_mut() {
             if !platform.is_empty() && train.front() == platform.track_location() {
                 platform.clear_cargo();
@@ -2,10 +2,12 @@ use random_trait::Random;
 
 use crate::{
     cargo::*,
+    effect::Effect,
     game_state::*,
     input::{InputDirection, InputEvent},
     modes::GameModeHandler,
     random::Rand,
+    train::Collision,
 };
 
 const START_SPEED: u8 = 5;
@@ -13,6 +15,7 @@ const START_SPEED: u8 = 5;
 pub struct JuggleMode {
     counter: u8,
     score: u16,
+    new_best: bool,
 }
 
 impl Default for JuggleMode {
@@ -20,6 +23,7 @@ impl Default for JuggleMode {
         JuggleMode {
             counter: 0,
             score: 0,
+            new_best: false,
         }
     }
 }
@@ -28,6 +32,7 @@ impl GameModeHandler for JuggleMode {
     fn on_restart(&mut self, state: &mut GameState) {
         self.counter = 0;
         self.score = 0;
+        self.new_best = false;
         state.is_over = false;
         state.is_paused = false;
         state.display = DisplayState::Score(self.score);
@@ -44,6 +49,8 @@ impl GameModeHandler for JuggleMode {
             if self.counter == 0 {
                 state.display = if state.is_paused {
                     DisplayState::PAUSE
+                } else if self.new_best {
+                    DisplayState::HI
                 } else {
                     DisplayState::GG
                 }
@@ -77,17 +84,7 @@ impl GameModeHandler for JuggleMode {
     }
 
     fn on_train_advance(&mut self, train_index: usize, state: &mut GameState) {
-        let train = &mut state.trains[train_index];
-
-        // TODO: this doesn't work here, but it does it snake mode?
-        // // Check if train collided with itself
-        // for i in 1..train.len() {
-        //     if train[i].loc == train.front() {
-        //         state.display = DisplayState::Text(*b"abc");
-        //         state.is_over = true;
-        //         return;
-        //     }
-        // }
+        let train = state.trains.get(train_index).unwrap();
 
         // Clear cargo if train front is at a platform with cargo
         let mut score_updated = false;
@@ -95,22 +92,25 @@ impl GameModeHandler for JuggleMode {
             if !platform.is_empty() && train.front() == platform.track_location() {
                 platform.clear_cargo();
 
+                // level up every 5 deliveries, wiping a quarter turn further each level
+                if (self.score + 1) % 5 == 0 {
+                    let direction = match (self.score + 1) / 5 % 4 {
+                        1 => InputDirection::Right,
+                        2 => InputDirection::Down,
+                        3 => InputDirection::Left,
+                        _ => InputDirection::Up,
+                    };
+                    state.effect = Some(Effect::wipe(direction));
+                } else {
+                    state.effect = Some(Effect::ripple(platform.location()));
+                }
+
                 score_updated = true;
                 self.score += 1;
                 state.display = DisplayState::Score(self.score);
             }
         }
 
-        // Check if train collided with another train
-        let train_front = train.front();
-        for (other_index, other_train) in state.trains.iter().enumerate() {
-            if train_index != other_index && other_train.at_location(train_front) {
-                state.display = DisplayState::Text(*b" GG");
-                state.is_over = true;
-                return;
-            }
-        }
-
         // difficulty scaling
         if score_updated {
             match self.score {
@@ -138,4 +138,15 @@ impl GameModeHandler for JuggleMode {
             }
         }
     }
+
+    fn on_collision(&mut self, collision: Collision, state: &mut GameState) {
+        self.new_best = state.record_score(self.score);
+        state.effect = Some(Effect::ripple(collision.location));
+        state.display = if self.new_best {
+            DisplayState::HI
+        } else {
+            DisplayState::Text(*b" GG")
+        };
+        state.is_over = true;
+    }
 }
@@ -14,9 +14,11 @@ use crate::{
 use as1115::segments::*;
 
 const IDLE_CYCLES: u16 = 400; // number of cycles before switching back to animation
+const RECORD_CYCLES: u16 = 100; // number of cycles between showing the mode name and its record
 const SNAKE_LENGTH: usize = 6; // number of segments in the snake
 const SNAKE_PERIOD: u8 = 15; // number of ticks between snake movements
 const MAX_NEXT_SEGMENTS: usize = 3; // max of 3 options when moving from one segment
+const STATION_DWELL_TICKS: u8 = 100; // ticks the attract loop trains wait at platforms they collect cargo at
 
 #[derive(Clone, Copy, Default)]
 struct SnakeLocation {
@@ -544,6 +546,13 @@ impl GameModeHandler for MenuMode {
             if self.counter > IDLE_CYCLES {
                 self.counter = 0;
                 self.index = 0;
+            } else if self.counter % RECORD_CYCLES == 0 {
+                // alternate between the selected mode's name and its record, if it has one
+                let name = GameMode::mode_name(self.index);
+                state.display = match state.high_scores.best(&name) {
+                    Some(best) if (self.counter / RECORD_CYCLES) % 2 == 1 => DisplayState::Score(best),
+                    _ => DisplayState::Text(name),
+                };
             }
         }
 
@@ -569,17 +578,24 @@ impl GameModeHandler for MenuMode {
                 }
                 _ => {}
             },
+            // back to the snake animation without waiting for the idle timeout
+            InputEvent::DirectionButtonDoubleTapped(InputDirection::Left) if self.index != 0 => {
+                self.index = 0;
+                state.display = DisplayState::Segments(self.snake_segment_data());
+            }
             _ => {}
         }
     }
 
     fn on_train_advance(&mut self, train_index: usize, state: &mut GameState) {
-        if Rand::default().get_bool() {
-            state.train_switch(train_index);
-        }
+        // attract loop demos the trains collecting cargo
+        state.autopilot(train_index);
+        state.auto_stop(train_index, STATION_DWELL_TICKS, |_, platform| {
+            !platform.is_empty()
+        });
 
         // Clear cargo if train front is at a platform with cargo
-        let train = &state.trains[train_index];
+        let train = state.trains.get(train_index).unwrap();
         for platform in state.platforms.iter_mut() {
             if !platform.is_empty() && train.front() == platform.track_location() {
                 platform.clear_cargo();
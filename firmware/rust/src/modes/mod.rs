@@ -1,22 +1,28 @@
-use crate::{game_state::*, input::InputEvent, NUM_DIGITS};
+use crate::{game_state::*, input::InputEvent, train::Collision, NUM_DIGITS};
 use enum_dispatch::enum_dispatch;
 
-//pub mod freeplay;
+#[cfg(feature = "mode_freeplay")]
+pub mod freeplay;
+#[cfg(feature = "mode_juggle")]
 pub mod juggle;
 pub mod menu;
 pub mod settings;
+#[cfg(feature = "mode_snake")]
 pub mod snake;
+#[cfg(feature = "mode_time")]
 pub mod time;
 
-//pub use freeplay::*;
+#[cfg(feature = "mode_freeplay")]
+pub use freeplay::*;
+#[cfg(feature = "mode_juggle")]
 pub use juggle::*;
 pub use menu::*;
 pub use settings::*;
+#[cfg(feature = "mode_snake")]
 pub use snake::*;
+#[cfg(feature = "mode_time")]
 pub use time::*;
 
-pub const NUM_MODES: usize = 5;
-
 #[enum_dispatch]
 pub trait GameModeHandler {
     // on restart
@@ -30,44 +36,75 @@ pub trait GameModeHandler {
 
     // when a train advances
     fn on_train_advance(&mut self, train_index: usize, state: &mut GameState);
-}
 
-#[enum_dispatch(GameModeHandler)]
-pub enum GameMode {
-    Menu(MenuMode),
-    //Freeplay(FreeplayMode),
-    Juggle(JuggleMode),
-    Snake(SnakeMode),
-    Time(TimeMode),
-    SettingsMode(SettingsMode),
+    // when a train runs into another train or itself, before the train advance callbacks of the tick
+    fn on_collision(&mut self, _collision: Collision, _state: &mut GameState) {}
 }
 
-impl Default for GameMode {
-    fn default() -> Self {
-        GameMode::Menu(MenuMode::default())
-    }
-}
+/// Registers the game modes in menu order, generating GameMode, NUM_MODES, and the index lookups.
+/// The menu is always index 0, and modes disabled by their cargo feature take no index.
+macro_rules! game_modes {
+    ($($(#[cfg($cfg:meta)])? $variant:ident($mode:ident) => $name:literal,)*) => {
+        #[enum_dispatch(GameModeHandler)]
+        pub enum GameMode {
+            Menu(MenuMode),
+            $($(#[cfg($cfg)])? $variant($mode),)*
+        }
 
-impl GameMode {
-    pub fn from_index(mode_index: usize) -> Self {
-        match mode_index + 1{
-            //1 => GameMode::Freeplay(FreeplayMode::default()),
-            2 => GameMode::Juggle(JuggleMode::default()),
-            3 => GameMode::Snake(SnakeMode::default()),
-            4 => GameMode::Time(TimeMode::default()),
-            5 => GameMode::SettingsMode(SettingsMode::default()),
-            _ => GameMode::Menu(MenuMode::default()),
+        // discriminants are the mode indices of the enabled modes
+        #[allow(dead_code)]
+        enum ModeIndex {
+            Menu,
+            $($(#[cfg($cfg)])? $variant,)*
+            Count,
         }
-    }
 
-    pub fn mode_name(mode_index: usize) -> [u8; NUM_DIGITS as usize] {
-        match mode_index + 1 {
-            //1 => *b"ply", // Play
-            2 => *b"jgl", // Juggle
-            3 => *b"snk", // Snake
-            4 => *b"tme", // Time (pick up and deliver)
-            5 => *b"set", // Settings
-            _ => *b"err",
+        pub const NUM_MODES: usize = ModeIndex::Count as usize;
+
+        // mode names are shown as text on the digits
+        $(
+            $(#[cfg($cfg)])?
+            const _: () = assert!($name.len() == NUM_DIGITS as usize, "mode name must be NUM_DIGITS long");
+        )*
+
+        impl GameMode {
+            pub fn from_index(mode_index: usize) -> Self {
+                match mode_index {
+                    $(
+                        $(#[cfg($cfg)])?
+                        i if i == ModeIndex::$variant as usize => GameMode::$variant($mode::default()),
+                    )*
+                    _ => GameMode::Menu(MenuMode::default()),
+                }
+            }
+
+            pub fn mode_name(mode_index: usize) -> [u8; NUM_DIGITS as usize] {
+                match mode_index {
+                    $(
+                        $(#[cfg($cfg)])?
+                        i if i == ModeIndex::$variant as usize => *$name,
+                    )*
+                    _ => *b"err",
+                }
+            }
         }
+    };
+}
+
+game_modes! {
+    #[cfg(feature = "mode_freeplay")]
+    Freeplay(FreeplayMode) => b"ply", // Play
+    #[cfg(feature = "mode_juggle")]
+    Juggle(JuggleMode) => b"jgl", // Juggle
+    #[cfg(feature = "mode_snake")]
+    Snake(SnakeMode) => b"snk", // Snake
+    #[cfg(feature = "mode_time")]
+    Time(TimeMode) => b"tme", // Time (pick up and deliver)
+    SettingsMode(SettingsMode) => b"set", // Settings
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        GameMode::Menu(MenuMode::default())
     }
 }
@@ -17,6 +17,10 @@ enum Setting {
     TrainBrightness,
     PlatformBrightness,
     SwitchBrightness,
+    GameSpeed,
+    SmoothMotion,
+    TrainStyle,
+    StationDwell,
     BuzzerEnabled,
 }
 
@@ -48,6 +52,26 @@ impl SettingsMode {
                 segments[1] = ascii_to_segment(b'B') | as1115::segments::DP;
                 segments[2] = ascii_to_segment(b'0' + settings.switch_brightness_level());
             }
+            Setting::GameSpeed => {
+                segments[0] = ascii_to_segment(b'G');
+                segments[1] = ascii_to_segment(b'S') | as1115::segments::DP;
+                segments[2] = ascii_to_segment(b'1' + settings.game_speed_level());
+            }
+            Setting::SmoothMotion => {
+                segments[0] = ascii_to_segment(b'S');
+                segments[1] = ascii_to_segment(b'M') | as1115::segments::DP;
+                segments[2] = ascii_to_segment(if settings.is_smooth_motion() { b'1' } else { b'0' });
+            }
+            Setting::TrainStyle => {
+                segments[0] = ascii_to_segment(b'T');
+                segments[1] = ascii_to_segment(b'S') | as1115::segments::DP;
+                segments[2] = ascii_to_segment(b'1' + settings.train_style_level());
+            }
+            Setting::StationDwell => {
+                segments[0] = ascii_to_segment(b'S');
+                segments[1] = ascii_to_segment(b'D') | as1115::segments::DP;
+                segments[2] = ascii_to_segment(b'0' + settings.station_dwell_level());
+            }
             Setting::BuzzerEnabled => {
                 segments[0] = ascii_to_segment(b'B');
                 segments[1] = ascii_to_segment(b'Z') | as1115::segments::DP;
@@ -62,7 +86,11 @@ impl SettingsMode {
             Setting::DigitBrightness => Setting::TrainBrightness,
             Setting::TrainBrightness => Setting::PlatformBrightness,
             Setting::PlatformBrightness => Setting::SwitchBrightness,
-            Setting::SwitchBrightness => Setting::BuzzerEnabled,
+            Setting::SwitchBrightness => Setting::GameSpeed,
+            Setting::GameSpeed => Setting::SmoothMotion,
+            Setting::SmoothMotion => Setting::TrainStyle,
+            Setting::TrainStyle => Setting::StationDwell,
+            Setting::StationDwell => Setting::BuzzerEnabled,
             Setting::BuzzerEnabled => Setting::DigitBrightness,
         };
     }
@@ -73,7 +101,11 @@ impl SettingsMode {
             Setting::TrainBrightness => Setting::DigitBrightness,
             Setting::PlatformBrightness => Setting::TrainBrightness,
             Setting::SwitchBrightness => Setting::PlatformBrightness,
-            Setting::BuzzerEnabled => Setting::SwitchBrightness,
+            Setting::GameSpeed => Setting::SwitchBrightness,
+            Setting::SmoothMotion => Setting::GameSpeed,
+            Setting::TrainStyle => Setting::SmoothMotion,
+            Setting::StationDwell => Setting::TrainStyle,
+            Setting::BuzzerEnabled => Setting::StationDwell,
         };
     }
 
@@ -91,6 +123,18 @@ impl SettingsMode {
             Setting::SwitchBrightness => {
                 settings.inc_switch_brightness_level();
             }
+            Setting::GameSpeed => {
+                settings.inc_game_speed_level();
+            }
+            Setting::SmoothMotion => {
+                settings.toggle_smooth_motion();
+            }
+            Setting::TrainStyle => {
+                settings.inc_train_style_level();
+            }
+            Setting::StationDwell => {
+                settings.inc_station_dwell_level();
+            }
             Setting::BuzzerEnabled => {
                 settings.toggle_buzzer();
             }
@@ -111,6 +155,18 @@ impl SettingsMode {
             Setting::SwitchBrightness => {
                 settings.dec_switch_brightness_level();
             }
+            Setting::GameSpeed => {
+                settings.dec_game_speed_level();
+            }
+            Setting::SmoothMotion => {
+                settings.toggle_smooth_motion();
+            }
+            Setting::TrainStyle => {
+                settings.dec_train_style_level();
+            }
+            Setting::StationDwell => {
+                settings.dec_station_dwell_level();
+            }
             Setting::BuzzerEnabled => {
                 settings.toggle_buzzer();
             }
@@ -158,10 +214,13 @@ impl GameModeHandler for SettingsMode {
         match event {
             InputEvent::DirectionButtonPressed(InputDirection::Up) => self.prev_setting(),
             InputEvent::DirectionButtonPressed(InputDirection::Down) => self.next_setting(),
-            InputEvent::DirectionButtonPressed(InputDirection::Left) => {
+            // repeat while held to change settings quickly
+            InputEvent::DirectionButtonPressed(InputDirection::Left)
+            | InputEvent::DirectionButtonRepeated(InputDirection::Left) => {
                 self.dec_setting(&mut state.settings)
             }
-            InputEvent::DirectionButtonPressed(InputDirection::Right) => {
+            InputEvent::DirectionButtonPressed(InputDirection::Right)
+            | InputEvent::DirectionButtonRepeated(InputDirection::Right) => {
                 self.inc_setting(&mut state.settings)
             }
             _ => return, // don't update display for other events
@@ -171,7 +230,7 @@ impl GameModeHandler for SettingsMode {
     }
 
     fn on_train_advance(&mut self, train_index: usize, state: &mut GameState) {
-        let train = &state.trains[train_index];
+        let train = state.trains.get(train_index).unwrap();
 
         // Clear cargo if train front is at a platform with cargo
         for platform in state.platforms.iter_mut() {
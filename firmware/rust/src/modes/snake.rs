@@ -2,15 +2,18 @@ use random_trait::Random;
 
 use crate::{
     cargo::*,
+    effect::Effect,
     game_state::*,
     input::{InputDirection, InputEvent},
     modes::GameModeHandler,
     random::Rand,
+    train::{Collision, TrainStyle},
 };
 
 pub struct SnakeMode {
     score: u16,
     counter: u8,
+    new_best: bool,
 }
 
 impl Default for SnakeMode {
@@ -18,6 +21,7 @@ impl Default for SnakeMode {
         SnakeMode {
             score: 0,
             counter: 0,
+            new_best: false,
         }
     }
 }
@@ -26,11 +30,13 @@ impl GameModeHandler for SnakeMode {
     fn on_restart(&mut self, state: &mut GameState) {
         self.counter = 0;
         self.score = 1;
+        self.new_best = false;
         state.is_over = false;
         state.is_paused = false;
         state.display = DisplayState::Score(self.score);
 
         state.init_trains(Cargo::Full(LedPattern::Solid), 1, MAX_CARS as u8);
+        state.train_style = Some(TrainStyle::Headlight); // the snake's head leads the way
         state.init_platforms(Cargo::Full(LedPattern::Solid));
     }
 
@@ -40,6 +46,8 @@ impl GameModeHandler for SnakeMode {
             if self.counter == 0 {
                 state.display = if state.is_paused {
                     DisplayState::PAUSE
+                } else if self.new_best {
+                    DisplayState::HI
                 } else {
                     DisplayState::DED
                 }
@@ -73,16 +81,7 @@ impl GameModeHandler for SnakeMode {
     }
 
     fn on_train_advance(&mut self, train_index: usize, state: &mut GameState) {
-        let train = &mut state.trains[train_index];
-
-        // Check if train collided with itself
-        for i in 1..train.len() {
-            if train[i].loc == train.front() {
-                state.display = DisplayState::DED;
-                state.is_over = true;
-                return;
-            }
-        }
+        let mut train = state.trains.get_mut(train_index).unwrap();
 
         // Clear cargo if train front is at a platform with cargo
         for platform in state.platforms.iter_mut() {
@@ -96,4 +95,15 @@ impl GameModeHandler for SnakeMode {
             }
         }
     }
+
+    fn on_collision(&mut self, collision: Collision, state: &mut GameState) {
+        self.new_best = state.record_score(self.score);
+        state.effect = Some(Effect::ripple(collision.location));
+        state.display = if self.new_best {
+            DisplayState::HI
+        } else {
+            DisplayState::DED
+        };
+        state.is_over = true;
+    }
 }
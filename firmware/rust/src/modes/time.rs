@@ -3,6 +3,8 @@ use random_trait::Random;
 
 use crate::{
     cargo::*,
+    clock,
+    effect::Effect,
     game_state::*,
     input::{InputDirection, InputEvent},
     location::NUM_PLATFORMS,
@@ -13,7 +15,7 @@ use crate::{
 
 pub struct CargoTimer {
     platform_index: u8,
-    ticks_left: u16,
+    ms_left: u32,
 }
 
 pub struct TimeMode {
@@ -21,22 +23,24 @@ pub struct TimeMode {
     score: u16,
     timers: Vec<CargoTimer, { TimeMode::MAX_TIMERS as usize }>,
     timer_dots: u8, // indicate time left with the 3 decimal points on display
+    new_best: bool,
+    last_tick: u32, // clock time of the last game tick, timers only run while the game does
 }
 
 impl TimeMode {
     const MAX_TIMERS: u8 = 5;
     const MAX_SPEED: u8 = 15;
     const SPEED_INC: u8 = 5;
-    const DEFAULT_TIMER_TICKS: u16 = 8000; // ~ 120 seconds with current runtime at 10ms base delay
+    const DEFAULT_TIMER_MS: u32 = 120_000;
 
     // difficulty calc functions
     #[inline(always)]
-    fn cargo_ticks_left(&self, cargo: Cargo) -> u16 {
+    fn cargo_ms_left(&self, cargo: Cargo) -> u32 {
         match cargo {
-            Cargo::Full(LedPattern::Blink1) => Self::DEFAULT_TIMER_TICKS >> 0,
-            Cargo::Full(LedPattern::Blink2) => Self::DEFAULT_TIMER_TICKS >> 1,
-            Cargo::Full(LedPattern::Blink3) => Self::DEFAULT_TIMER_TICKS >> 2,
-            _ => Self::DEFAULT_TIMER_TICKS,
+            Cargo::Full(LedPattern::Blink1) => Self::DEFAULT_TIMER_MS >> 0,
+            Cargo::Full(LedPattern::Blink2) => Self::DEFAULT_TIMER_MS >> 1,
+            Cargo::Full(LedPattern::Blink3) => Self::DEFAULT_TIMER_MS >> 2,
+            _ => Self::DEFAULT_TIMER_MS,
         }
     }
 
@@ -56,7 +60,7 @@ impl TimeMode {
         20 + self.score / 10
     }
 
-    fn add_platform_timer(&mut self, platform_index: u8, ticks_left: u16) {
+    fn add_platform_timer(&mut self, platform_index: u8, ms_left: u32) {
         if self.timers.is_full()
             || self
                 .timers
@@ -68,7 +72,7 @@ impl TimeMode {
 
         let timer = CargoTimer {
             platform_index,
-            ticks_left,
+            ms_left,
         };
         self.timers.push(timer).ok();
     }
@@ -110,6 +114,8 @@ impl Default for TimeMode {
             score: 0,
             timers: Vec::new(),
             timer_dots: NUM_DIGITS,
+            new_best: false,
+            last_tick: 0,
         }
     }
 }
@@ -120,6 +126,8 @@ impl GameModeHandler for TimeMode {
         self.score = 0;
         self.timer_dots = NUM_DIGITS;
         self.timers.clear();
+        self.new_best = false;
+        self.last_tick = clock::now();
 
         state.is_over = false;
         state.is_paused = false;
@@ -129,6 +137,10 @@ impl GameModeHandler for TimeMode {
     }
 
     fn on_game_tick(&mut self, state: &mut GameState) {
+        let now = clock::now();
+        let elapsed = now.wrapping_sub(self.last_tick);
+        self.last_tick = now;
+
         if state.is_over || state.is_paused {
             self.counter += 1;
             if self.counter == 0 {
@@ -136,6 +148,8 @@ impl GameModeHandler for TimeMode {
                     let mut segment_data = DisplayState::PAUSE_BYTES;
                     self.add_timer_indicators(&mut segment_data);
                     DisplayState::Segments(segment_data)
+                } else if self.new_best {
+                    DisplayState::HI
                 } else {
                     DisplayState::DED
                 }
@@ -147,29 +161,38 @@ impl GameModeHandler for TimeMode {
 
         let mut timer_update = false;
         for timer in self.timers.iter_mut() {
-            timer.ticks_left = timer.ticks_left.saturating_sub(1);
+            let ms_before = timer.ms_left;
+            timer.ms_left = timer.ms_left.saturating_sub(elapsed);
+            let ms_left = timer.ms_left;
+            let crossed = |threshold: u32| ms_before > threshold && ms_left <= threshold;
 
             let timer_platform = &mut state.platforms[timer.platform_index as usize];
-            if timer.ticks_left == (Self::DEFAULT_TIMER_TICKS >> 1) {
+            if crossed(Self::DEFAULT_TIMER_MS >> 1) {
                 timer_platform.set_phase_speed(2);
                 if self.timer_dots > 2 {
                     self.timer_dots = 2;
                     timer_update = true;
                 }
-            } else if timer.ticks_left == (Self::DEFAULT_TIMER_TICKS >> 2) {
+            } else if crossed(Self::DEFAULT_TIMER_MS >> 2) {
                 timer_platform.set_phase_speed(3);
                 if self.timer_dots > 1 {
                     self.timer_dots = 1;
                     timer_update = true;
                 }
-            } else if timer.ticks_left == (Self::DEFAULT_TIMER_TICKS >> 3) {
+            } else if crossed(Self::DEFAULT_TIMER_MS >> 3) {
                 timer_platform.set_phase_speed(6);
                 if self.timer_dots > 0 {
                     self.timer_dots = 0;
                     timer_update = true;
                 }
-            } else if timer.ticks_left == 0 {
-                state.display = DisplayState::OVR;
+            } else if ms_left == 0 {
+                state.effect = Some(Effect::ripple(timer_platform.location()));
+                self.new_best = state.record_score(self.score);
+                state.display = if self.new_best {
+                    DisplayState::HI
+                } else {
+                    DisplayState::OVR
+                };
                 state.is_over = true;
                 return;
             }
@@ -196,14 +219,14 @@ impl GameModeHandler for TimeMode {
                     platform.set_cargo_out(spawned_cargo);
                     self.add_platform_timer(
                         platform_index as u8,
-                        self.cargo_ticks_left(spawned_cargo),
+                        self.cargo_ms_left(spawned_cargo),
                     );
                 }
             }
         }
 
         // if train is stopped
-        let train = &mut state.trains[0];
+        let mut train = state.trains.get_mut(0).unwrap();
         if train.speed() == 0 {
             let mut cargo_to_place: Vec<Cargo, NUM_PLATFORMS> = Vec::new();
 
@@ -228,8 +251,10 @@ impl GameModeHandler for TimeMode {
                                 }
                                 state.display = self.score_display();
 
+                                // level up with a longer train and a wipe across the board
                                 if self.score == 3 || self.score % 10 == 0 {
                                     train.add_car(Cargo::Empty);
+                                    state.effect = Some(Effect::wipe(InputDirection::Right));
                                 }
                             }
                         } else {
@@ -261,7 +286,7 @@ impl GameModeHandler for TimeMode {
                     // cargo with more blinks has shorter timer and more points awarded when delivered
                     self.add_platform_timer(
                         rand_platform_index as u8,
-                        self.cargo_ticks_left(cargo),
+                        self.cargo_ms_left(cargo),
                     );
                     available_platform_indices.remove(rand_index);
                 }
@@ -275,15 +300,40 @@ impl GameModeHandler for TimeMode {
         }
 
         match event {
+            // repeat while held to change speed quickly, the train accelerates and brakes to the new speed
+            InputEvent::DirectionButtonRepeated(InputDirection::Left) => {
+                let speed = state.trains[0].target_speed();
+                state.trains[0].set_target_speed(speed.saturating_sub(Self::SPEED_INC));
+            }
+            InputEvent::DirectionButtonRepeated(InputDirection::Right) => {
+                let speed = state.trains[0].target_speed();
+                let new_speed = speed.saturating_add(Self::SPEED_INC).min(Self::MAX_SPEED);
+                state.trains[0].set_target_speed(new_speed);
+            }
+            // hold while stopped to turn the train around and back it up, e.g. onto a platform behind it
+            InputEvent::DirectionButtonHeld(InputDirection::Left | InputDirection::Right)
+                if state.trains[0].speed() == 0 =>
+            {
+                if let Some(mut train) = state.trains.get_mut(0) {
+                    train.reverse();
+                }
+            }
+            // double tap to stop or go full speed
+            InputEvent::DirectionButtonDoubleTapped(InputDirection::Left) => {
+                state.trains[0].set_target_speed(0);
+            }
+            InputEvent::DirectionButtonDoubleTapped(InputDirection::Right) => {
+                state.trains[0].set_target_speed(Self::MAX_SPEED);
+            }
             InputEvent::DirectionButtonPressed(direction) => match direction {
                 InputDirection::Left => {
-                    let speed = state.trains[0].speed();
-                    state.trains[0].set_speed(speed.saturating_sub(Self::SPEED_INC));
+                    let speed = state.trains[0].target_speed();
+                    state.trains[0].set_target_speed(speed.saturating_sub(Self::SPEED_INC));
                 }
                 InputDirection::Right => {
-                    let speed = state.trains[0].speed();
+                    let speed = state.trains[0].target_speed();
                     let new_speed = speed.saturating_add(Self::SPEED_INC).min(Self::MAX_SPEED);
-                    state.trains[0].set_speed(new_speed);
+                    state.trains[0].set_target_speed(new_speed);
                 }
                 InputDirection::Up | InputDirection::Down => {
                     state.is_paused = !state.is_paused;
@@ -293,5 +343,14 @@ impl GameModeHandler for TimeMode {
         }
     }
 
-    fn on_train_advance(&mut self, _: usize, _: &mut GameState) {}
+    fn on_train_advance(&mut self, train_index: usize, state: &mut GameState) {
+        // easier play, the train stops by itself where it can load or unload and the tick loads it
+        if let Some(dwell_ticks) = state.settings.station_dwell_ticks() {
+            state.auto_stop(train_index, dwell_ticks, |train, platform| {
+                let (cargo, is_receiving) = platform.cargo();
+                let wanted = if is_receiving { cargo } else { Cargo::Empty };
+                cargo != Cargo::Empty && train.cars().iter().any(|car| car.cargo == wanted)
+            });
+        }
+    }
 }
@@ -1,13 +1,17 @@
+use light_rail::board::Buzzer;
+
 pub struct TimerTone {}
 
 impl TimerTone {
     pub fn new() -> Self {
         Self {}
     }
+}
 
-    pub fn tone(&mut self, _: u16, _: u16) {
+impl Buzzer for TimerTone {
+    fn tone(&mut self, _: u16, _: u16) {
     }
 
-    pub fn no_tone(&mut self) {
+    fn no_tone(&mut self) {
     }
 }
@@ -0,0 +1,107 @@
+use crate::{
+    game_state::MAX_TRAINS,
+    location::{Location, NUM_LOCATION_NODES},
+};
+
+const MAP_SIZE: usize = NUM_LOCATION_NODES.div_ceil(8);
+const OWNERS_SIZE: usize = NUM_LOCATION_NODES.div_ceil(2);
+const NO_OWNER: u8 = 0xF;
+
+// owners are packed in nibbles
+const _: () = assert!(MAX_TRAINS < NO_OWNER as usize);
+
+/// Bitmap of the locations occupied by train cars, with the index of the train owning each location.
+/// Kept up to date by Trains as cars move, so location lookups don't scan every car.
+/// A location holding more than one car (only after a collision, or trains passing through each other)
+/// is marked shared, its owner is one of the trains there.
+pub struct Occupancy {
+    occupied: [u8; MAP_SIZE],
+    shared: [u8; MAP_SIZE],
+    owners: [u8; OWNERS_SIZE],
+}
+
+impl Default for Occupancy {
+    fn default() -> Self {
+        Self {
+            occupied: [0; MAP_SIZE],
+            shared: [0; MAP_SIZE],
+            owners: [NO_OWNER << 4 | NO_OWNER; OWNERS_SIZE],
+        }
+    }
+}
+
+impl Occupancy {
+    /// Returns true if any car is at the location
+    pub fn is_occupied(&self, loc: Location) -> bool {
+        Self::index(loc).is_some_and(|index| Self::get_bit(&self.occupied, index))
+    }
+
+    /// Returns true if more than one car is at the location
+    pub fn is_shared(&self, loc: Location) -> bool {
+        Self::index(loc).is_some_and(|index| Self::get_bit(&self.shared, index))
+    }
+
+    /// Returns the index of the train at the location
+    pub fn owner(&self, loc: Location) -> Option<usize> {
+        let index = Self::index(loc)?;
+        let owner = (self.owners[index / 2] >> ((index % 2) * 4)) & NO_OWNER;
+        if owner == NO_OWNER {
+            None
+        } else {
+            Some(owner as usize)
+        }
+    }
+
+    /// Marks a car of the train entering the location, returns true if the location was already occupied
+    pub fn enter(&mut self, loc: Location, train_index: usize) -> bool {
+        if self.is_occupied(loc) {
+            self.set(loc, self.owner(loc), true);
+            true
+        } else {
+            self.set(loc, Some(train_index), false);
+            false
+        }
+    }
+
+    /// Sets the state of the location, an owner of None clears it
+    pub fn set(&mut self, loc: Location, owner: Option<usize>, shared: bool) {
+        let Some(index) = Self::index(loc) else {
+            return;
+        };
+
+        Self::set_bit(&mut self.occupied, index, owner.is_some());
+        Self::set_bit(&mut self.shared, index, shared && owner.is_some());
+
+        let shift = (index % 2) * 4;
+        let owner = owner.map_or(NO_OWNER, |owner| owner as u8);
+        self.owners[index / 2] = (self.owners[index / 2] & !(NO_OWNER << shift)) | (owner << shift);
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    #[inline(always)]
+    fn index(loc: Location) -> Option<usize> {
+        let index = loc.index() as usize;
+        if index < NUM_LOCATION_NODES {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    #[inline(always)]
+    fn get_bit(map: &[u8; MAP_SIZE], index: usize) -> bool {
+        map[index / 8] & (1 << (index % 8)) != 0
+    }
+
+    #[inline(always)]
+    fn set_bit(map: &mut [u8; MAP_SIZE], index: usize, value: bool) {
+        if value {
+            map[index / 8] |= 1 << (index % 8);
+        } else {
+            map[index / 8] &= !(1 << (index % 8));
+        }
+    }
+}
@@ -2,22 +2,9 @@ use core::ptr::addr_of;
 use atmega_hal::delay;
 use embedded_hal::delay::DelayNs;
 
-use crate::{Delay, I2c, NUM_DIGITS, DIGITS_I2C_ADDR, DIGITS_INTENSITY};
+use light_rail::NUM_DIGITS;
 
-#[macro_export]
-macro_rules! panic_with_error {
-    ($error_code:expr) => {{
-        crate::panic::set_error_code($error_code);
-        panic!()
-    }};
-}
-
-static mut ERROR_CODE: u16 = 0;
-pub fn set_error_code(error_code: u16) {
-    unsafe {
-        ERROR_CODE = error_code;
-    }
-}
+use crate::{Delay, I2c, DIGITS_I2C_ADDR, DIGITS_INTENSITY};
 
 #[panic_handler]
 fn panic(_: &core::panic::PanicInfo) -> ! {
@@ -41,10 +28,7 @@ fn panic(_: &core::panic::PanicInfo) -> ! {
     let mut board_digits = as1115::AS1115::new(i2c, DIGITS_I2C_ADDR);
     board_digits.init(NUM_DIGITS, DIGITS_INTENSITY).ok();
 
-    let mut error_code: u16 = 0;
-    unsafe {
-        error_code = ERROR_CODE;
-    }
+    let error_code = light_rail::error_code();
 
     loop {
         board_digits.display_ascii(b"err").ok();
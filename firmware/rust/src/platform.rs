@@ -10,7 +10,8 @@ pub struct Platform {
     cargo: Cargo,
     is_cargo_in: bool, // is the current cargo going out/shipping, or coming in/receiving?
     last_brightness: u8,
-    phase: u8,     // phase of the platform, used for PWM
+    last_blink: bool, // last drawn blinking in hardware
+    phase: u16,    // fixed-point phase of the platform, high byte used for PWM
     phase_inc: u8, // phase increment for speed control
 }
 
@@ -22,6 +23,7 @@ impl Platform {
             cargo: Cargo::Empty,
             is_cargo_in: false,
             last_brightness: 0,
+            last_blink: false,
             phase: 0, // initial phase
             phase_inc: 1,                    // default increment
         }
@@ -43,34 +45,45 @@ impl Platform {
         platforms
     }
 
+    /// Draws the platform, update_callback gets the location, brightness, and whether the LED blinks in hardware.
+    /// With hardware_blink, patterns the LED driver can blink are drawn once instead of each tick.
     pub fn update<F>(
         &mut self,
         settings: &GameSettings,
         mut update_callback: F,
         force_update: bool,
+        hardware_blink: bool,
     ) -> bool
     where
-        F: FnMut(Location, u8),
+        F: FnMut(Location, u8, bool),
     {
-        self.phase = self.phase.wrapping_add(self.phase_inc);
+        self.phase = settings.advance_phase(self.phase, self.phase_inc);
+        let phase = (self.phase >> 8) as u8;
 
         // cargo coming in has an inverse pattern of blinking
-        let brightness = if self.is_cargo_in {
-            self.cargo.platform_brightness(
-                self.phase,
-                settings.platform_brightness() >> 1,
-                0,
-            )
+        let min_b = settings.platform_brightness() >> 1;
+        let max_b = if self.is_cargo_in {
+            0
         } else {
-            self.cargo.platform_brightness(
-                self.phase,
-                settings.platform_brightness() >> 1,
-                settings.platform_brightness(),
-            )
+            settings.platform_brightness()
         };
-        if force_update || brightness != self.last_brightness {
+        // the LED driver blinks at one rate for the game speed, sped up platforms blink in software
+        let blink_brightness = match self.cargo {
+            Cargo::Full(pattern)
+                if hardware_blink && self.phase_inc == 1 && settings.blink_period().is_some() =>
+            {
+                pattern.hardware_blink(min_b, max_b)
+            }
+            _ => None,
+        };
+        let brightness =
+            blink_brightness.unwrap_or_else(|| self.cargo.platform_brightness(phase, min_b, max_b));
+        let blink = blink_brightness.is_some();
+
+        if force_update || brightness != self.last_brightness || blink != self.last_blink {
             self.last_brightness = brightness;
-            update_callback(self.location, brightness);
+            self.last_blink = blink;
+            update_callback(self.location, brightness, blink);
             true
         } else {
             false
@@ -85,9 +98,9 @@ impl Platform {
         (self.cargo, self.is_cargo_in)
     }
 
-    // pub fn location(&self) -> Location {
-    //     self.location
-    // }
+    pub fn location(&self) -> Location {
+        self.location
+    }
 
     pub fn track_location(&self) -> Location {
         self.track_location
@@ -0,0 +1,144 @@
+use heapless::Vec;
+
+use crate::board::Storage;
+
+// slot layout: sequence number, data length, data padded to capacity, CRC-8 of sequence through data
+const SLOT_OVERHEAD: u16 = 3;
+const ERASED: u8 = 0xFF;
+
+pub enum RecordError {
+    Missing, // all slots erased, nothing has been saved yet
+    Corrupt, // no slot has a valid checksum
+}
+
+/// CRC-8 with polynomial 0x07
+fn crc8(crc: u8, data: &[u8]) -> u8 {
+    let mut crc = crc;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Wear-leveled storage for a small record of up to CAPACITY bytes, rotated across a region of slots.
+/// Each save goes to the slot after the newest one with the next sequence number, so every slot wears
+/// at 1/slots the rate of a fixed location. Loads use the newest slot with a valid checksum, so an
+/// interrupted save falls back to the previous record.
+pub struct RecordRing<const CAPACITY: usize> {
+    offset: u16,
+    slots: u8,
+}
+
+impl<const CAPACITY: usize> RecordRing<CAPACITY> {
+    pub const fn new(offset: u16, slots: u8) -> Self {
+        Self { offset, slots }
+    }
+
+    /// Returns the offset after the last slot, for laying out the next region.
+    pub const fn end(&self) -> u16 {
+        self.offset + self.slots as u16 * (CAPACITY as u16 + SLOT_OVERHEAD)
+    }
+
+    pub fn load<EEPROM>(&self, eeprom: &EEPROM) -> Result<Vec<u8, CAPACITY>, RecordError>
+    where
+        EEPROM: Storage,
+    {
+        if let Some((_, _, data)) = self.newest(eeprom) {
+            return Ok(data);
+        }
+
+        let erased = (0..self.slots).all(|slot| {
+            let offset = self.slot_offset(slot);
+            eeprom.read_byte(offset) == ERASED && eeprom.read_byte(offset + 1) == ERASED
+        });
+        Err(if erased {
+            RecordError::Missing
+        } else {
+            RecordError::Corrupt
+        })
+    }
+
+    /// Saves the record to the next slot, nothing is written if it matches the newest record.
+    pub fn save<EEPROM>(&self, eeprom: &mut EEPROM, data: &[u8])
+    where
+        EEPROM: Storage,
+    {
+        let data = &data[..data.len().min(CAPACITY)];
+        let (slot, seq) = match self.newest(eeprom) {
+            Some((_, _, ref current)) if current.as_slice() == data => return,
+            Some((slot, seq, _)) => ((slot + 1) % self.slots, seq.wrapping_add(1)),
+            None => (0, 0),
+        };
+
+        let len = data.len() as u8;
+        let crc = crc8(crc8(0, &[seq, len]), data);
+
+        let offset = self.slot_offset(slot);
+        eeprom.update_byte(offset, seq);
+        eeprom.update_byte(offset + 1, len);
+        for (i, &byte) in data.iter().enumerate() {
+            eeprom.update_byte(offset + 2 + i as u16, byte);
+        }
+        eeprom.update_byte(offset + 2 + CAPACITY as u16, crc);
+    }
+
+    #[inline(always)]
+    fn slot_offset(&self, slot: u8) -> u16 {
+        self.offset + slot as u16 * (CAPACITY as u16 + SLOT_OVERHEAD)
+    }
+
+    /// Returns the sequence number and data of the slot if its checksum is valid.
+    fn read_slot<EEPROM>(&self, eeprom: &EEPROM, slot: u8) -> Option<(u8, Vec<u8, CAPACITY>)>
+    where
+        EEPROM: Storage,
+    {
+        let offset = self.slot_offset(slot);
+        let seq = eeprom.read_byte(offset);
+        let len = eeprom.read_byte(offset + 1);
+        if len as usize > CAPACITY {
+            return None;
+        }
+
+        let mut data = Vec::new();
+        for i in 0..len as u16 {
+            data.push(eeprom.read_byte(offset + 2 + i)).ok();
+        }
+        let crc = eeprom.read_byte(offset + 2 + CAPACITY as u16);
+        if crc8(crc8(0, &[seq, len]), &data) != crc {
+            return None;
+        }
+        Some((seq, data))
+    }
+
+    /// Returns the slot, sequence number, and data of the newest valid record.
+    /// Slots are written in order, so the newest is a valid slot not followed by its next sequence number.
+    /// A corrupt slot also breaks the sequence, so the later of the candidates by sequence number wins.
+    fn newest<EEPROM>(&self, eeprom: &EEPROM) -> Option<(u8, u8, Vec<u8, CAPACITY>)>
+    where
+        EEPROM: Storage,
+    {
+        let mut newest: Option<(u8, u8, Vec<u8, CAPACITY>)> = None;
+        for slot in 0..self.slots {
+            if let Some((seq, data)) = self.read_slot(eeprom, slot) {
+                let next_seq = self
+                    .read_slot(eeprom, (slot + 1) % self.slots)
+                    .map(|(next_seq, _)| next_seq);
+                if next_seq == Some(seq.wrapping_add(1)) {
+                    continue;
+                }
+
+                let is_newer = match newest {
+                    Some((_, newest_seq, _)) => seq.wrapping_sub(newest_seq) < 0x80,
+                    None => true,
+                };
+                if is_newer {
+                    newest = Some((slot, seq, data));
+                }
+            }
+        }
+        newest
+    }
+}
@@ -0,0 +1,242 @@
+use heapless::Deque;
+
+use crate::{
+    location::{Direction, Location, NUM_LOCATION_NODES, NUM_SWITCHES},
+    switch::Switch,
+};
+
+// search states are a location and the direction of travel from it
+const VISITED_SIZE: usize = (NUM_LOCATION_NODES * 2).div_ceil(8);
+// each queued state ends a distinct branch of the search, and branches only split at the forks of the
+// switches, one way each, so at most 1 + 2 * NUM_SWITCHES are in flight at once
+const QUEUE_SIZE: usize = 4 * NUM_SWITCHES;
+
+// required switch states are kept as bits per switch and direction
+const _: () = assert!(NUM_SWITCHES * 2 <= u16::BITS as usize);
+
+/// Shortest route from a location and direction of travel, with the switch states it depends on.
+/// Found with a breadth-first search over the track graph, using a visited bitmap and a small queue
+/// instead of per-location tables to fit in AVR memory.
+#[derive(Clone, Copy)]
+pub struct Route {
+    end: Location,
+    direction: Direction, // direction of travel from the end location
+    distance: u8,
+    constrained: u16, // switch directions the route passes through a fork of
+    switched: u16,    // required states of the constrained switch directions
+}
+
+impl Route {
+    /// Finds the shortest route to the target location, or None if it is further than max_distance moves.
+    pub fn find(
+        start: Location,
+        direction: Direction,
+        target: Location,
+        max_distance: u8,
+    ) -> Option<Route> {
+        Self::find_nearest(start, direction, max_distance, |loc| loc == target)
+    }
+
+    /// Finds the shortest route to any location accepted by is_target, or None if there is none within
+    /// max_distance moves.
+    pub fn find_nearest<F>(
+        start: Location,
+        direction: Direction,
+        max_distance: u8,
+        is_target: F,
+    ) -> Option<Route>
+    where
+        F: Fn(Location) -> bool,
+    {
+        Self::search(
+            start,
+            direction,
+            max_distance,
+            |route| is_target(route.end),
+            |_| false,
+        )
+    }
+
+    /// Finds the shortest route to any location accepted by is_target that doesn't pass through a blocked
+    /// location, or None if there is none within max_distance moves.
+    pub fn find_nearest_clear<F, B>(
+        start: Location,
+        direction: Direction,
+        max_distance: u8,
+        is_target: F,
+        is_blocked: B,
+    ) -> Option<Route>
+    where
+        F: Fn(Location) -> bool,
+        B: Fn(Location) -> bool,
+    {
+        Self::search(
+            start,
+            direction,
+            max_distance,
+            |route| is_target(route.end),
+            is_blocked,
+        )
+    }
+
+    /// Finds a route of the given distance that doesn't pass through a blocked location, or None if
+    /// every way ahead is blocked.
+    pub fn find_clear<B>(
+        start: Location,
+        direction: Direction,
+        distance: u8,
+        is_blocked: B,
+    ) -> Option<Route>
+    where
+        B: Fn(Location) -> bool,
+    {
+        Self::search(
+            start,
+            direction,
+            distance,
+            |route| route.distance >= distance,
+            is_blocked,
+        )
+    }
+
+    fn search<F, B>(
+        start: Location,
+        direction: Direction,
+        max_distance: u8,
+        is_target: F,
+        is_blocked: B,
+    ) -> Option<Route>
+    where
+        F: Fn(&Route) -> bool,
+        B: Fn(Location) -> bool,
+    {
+        let switch_locs = Location::switch_locs();
+        let mut visited = [0u8; VISITED_SIZE];
+        let mut queue: Deque<Route, QUEUE_SIZE> = Deque::new();
+
+        Self::visit(&mut visited, start, direction);
+        queue
+            .push_back(Route {
+                end: start,
+                direction,
+                distance: 0,
+                constrained: 0,
+                switched: 0,
+            })
+            .ok();
+
+        // routes are queued in order of distance, so the first to reach a target is the shortest
+        while let Some(route) = queue.pop_front() {
+            if is_target(&route) {
+                return Some(route);
+            }
+            if route.distance >= max_distance {
+                continue;
+            }
+
+            let straight = route.end.next(route.direction, false);
+            let fork = route.end.next(route.direction, true);
+            let fork_bit = switch_locs
+                .iter()
+                .position(|&loc| loc == route.end)
+                .filter(|_| fork.0 != straight.0)
+                .map(|switch_index| Self::switch_bit(switch_index, route.direction));
+
+            for (switched, (next_loc, next_dir)) in [(false, straight), (true, fork)] {
+                if switched && fork_bit.is_none() {
+                    break;
+                }
+                if is_blocked(next_loc) || !Self::visit(&mut visited, next_loc, next_dir) {
+                    continue;
+                }
+
+                let mut next = Route {
+                    end: next_loc,
+                    direction: next_dir,
+                    distance: route.distance + 1,
+                    ..route
+                };
+                if let Some(bit) = fork_bit {
+                    next.constrained |= bit;
+                    if switched {
+                        next.switched |= bit;
+                    }
+                }
+                // a dropped state could lose the shortest route, so a full queue fails the search
+                if queue.push_back(next).is_err() {
+                    return None;
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the location the route ends at
+    pub fn end(&self) -> Location {
+        self.end
+    }
+
+    /// Returns the direction of travel from the end location
+    pub fn end_direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns the number of moves from the start to the end of the route
+    pub fn distance(&self) -> u8 {
+        self.distance
+    }
+
+    /// Returns the state the switch needs in the direction for the route, or None if the route doesn't
+    /// pass through its fork in that direction
+    pub fn required_state(&self, switch_index: usize, direction: Direction) -> Option<bool> {
+        let bit = Self::switch_bit(switch_index, direction);
+        if self.constrained & bit != 0 {
+            Some(self.switched & bit != 0)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the switch index, direction, and required state of every switch the route depends on
+    pub fn required_states(&self) -> impl Iterator<Item = (usize, Direction, bool)> + '_ {
+        (0..NUM_SWITCHES).flat_map(move |switch_index| {
+            [Direction::Anode, Direction::Cathode]
+                .into_iter()
+                .filter_map(move |direction| {
+                    self.required_state(switch_index, direction)
+                        .map(|switched| (switch_index, direction, switched))
+                })
+        })
+    }
+
+    /// Returns true if all switches are in the states the route needs
+    pub fn is_set(&self, switches: &[Switch]) -> bool {
+        self.required_states()
+            .all(|(switch_index, direction, switched)| {
+                switches[switch_index].is_switched(direction) == switched
+            })
+    }
+
+    /// Sets the switches to the states the route needs
+    pub fn set_switches(&self, switches: &mut [Switch]) {
+        for (switch_index, direction, switched) in self.required_states() {
+            switches[switch_index].set_switched(direction, switched);
+        }
+    }
+
+    #[inline(always)]
+    fn switch_bit(switch_index: usize, direction: Direction) -> u16 {
+        1 << (switch_index * 2 + direction as usize)
+    }
+
+    /// Marks the state visited, returns false if it already was
+    fn visit(visited: &mut [u8; VISITED_SIZE], loc: Location, direction: Direction) -> bool {
+        let index = loc.index() as usize * 2 + direction as usize;
+        let mask = 1 << (index % 8);
+        if visited[index / 8] & mask != 0 {
+            return false;
+        }
+        visited[index / 8] |= mask;
+        true
+    }
+}
@@ -0,0 +1,124 @@
+/// In-memory fakes of the board components for running the game on a host.
+/// Each fake records what the game last wrote so a host runner can render or inspect it.
+use as1115::{ascii_to_segment, NUMBERS};
+
+use crate::{
+    board::{ButtonSource, Buzzer, DigitDisplay, LedSink, Storage},
+    location::NUM_LOCATION_NODES,
+    NUM_BUTTONS, NUM_DIGITS,
+};
+
+// segment bits for host renderers decoding SimDigits
+pub use as1115::segments;
+
+const EEPROM_SIZE: usize = 1024; // ATmega32U4 EEPROM size
+
+pub struct SimLeds {
+    pub pixels: [u8; NUM_LOCATION_NODES],
+}
+
+impl Default for SimLeds {
+    fn default() -> Self {
+        Self {
+            pixels: [0; NUM_LOCATION_NODES],
+        }
+    }
+}
+
+impl LedSink for SimLeds {
+    fn clear(&mut self) {
+        self.pixels = [0; NUM_LOCATION_NODES];
+    }
+
+    fn set_pixel(&mut self, index: u8, brightness: u8) {
+        if let Some(pixel) = self.pixels.get_mut(index as usize) {
+            *pixel = brightness;
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SimDigits {
+    pub segments: [u8; NUM_DIGITS as usize],
+    pub brightness: u8,
+}
+
+impl DigitDisplay for SimDigits {
+    fn clear(&mut self) {
+        self.segments = [0; NUM_DIGITS as usize];
+    }
+
+    fn set_brightness(&mut self, level: u8) {
+        self.brightness = level;
+    }
+
+    fn display_number(&mut self, number: u16) {
+        // right aligned without leading zeros, same as the AS1115 driver
+        let mut number = number;
+        for digit in (0..NUM_DIGITS as usize).rev() {
+            self.segments[digit] = if number > 0 || digit == NUM_DIGITS as usize - 1 {
+                NUMBERS[(number % 10) as usize]
+            } else {
+                0
+            };
+            number /= 10;
+        }
+    }
+
+    fn display_raw(&mut self, segments: &[u8; NUM_DIGITS as usize]) {
+        self.segments = *segments;
+    }
+
+    fn display_ascii(&mut self, text: &[u8; NUM_DIGITS as usize]) {
+        self.segments = text.map(ascii_to_segment);
+    }
+}
+
+#[derive(Default)]
+pub struct SimButtons {
+    pub pressed: [bool; NUM_BUTTONS],
+}
+
+impl ButtonSource for SimButtons {
+    fn is_pressed(&mut self, index: usize) -> bool {
+        self.pressed[index]
+    }
+}
+
+#[derive(Default)]
+pub struct SimBuzzer {
+    pub last_tone: Option<(u16, u16)>,
+}
+
+impl Buzzer for SimBuzzer {
+    fn tone(&mut self, frequency: u16, duration: u16) {
+        self.last_tone = Some((frequency, duration));
+    }
+
+    fn no_tone(&mut self) {
+        self.last_tone = None;
+    }
+}
+
+pub struct SimEeprom {
+    pub bytes: [u8; EEPROM_SIZE],
+}
+
+impl Default for SimEeprom {
+    fn default() -> Self {
+        // erased EEPROM reads as 0xFF
+        Self {
+            bytes: [0xFF; EEPROM_SIZE],
+        }
+    }
+}
+
+impl Storage for SimEeprom {
+    fn read_byte(&self, offset: u16) -> u8 {
+        self.bytes[offset as usize]
+    }
+
+    fn write_byte(&mut self, offset: u16, data: u8) {
+        self.bytes[offset as usize] = data;
+    }
+}
@@ -5,12 +5,12 @@ use crate::{
     game_settings::GameSettings,
     location::{Direction, Location, NUM_SWITCHES},
     random::Rand,
-    train::Train,
+    train::Trains,
 };
 
 pub struct Switch {
     location: Location,
-    phase: u8, // phase of the switch, used for PWM
+    phase: u16, // fixed-point phase of the switch, high byte used for PWM
 
     // switches only have one active direction (one direction has None values)
     // crosses have two active directions
@@ -112,7 +112,7 @@ impl Switch {
     pub fn update<F>(
         &mut self,
         settings: &GameSettings,
-        trains: &[Train],
+        trains: &Trains,
         mut update_callback: F,
         force_update: bool,
     ) -> bool
@@ -120,7 +120,8 @@ impl Switch {
         F: FnMut(Location, u8),
     {
         let mut update = false;
-        self.phase = self.phase.wrapping_add(1);
+        self.phase = settings.advance_phase(self.phase, 1);
+        let phase = (self.phase >> 8) as u8;
 
         let mut handle_direction =
             |is_switched: Option<bool>,
@@ -142,19 +143,16 @@ impl Switch {
                         (next_location, fork_location.unwrap())
                     };
 
-                    // Only update inactive_loc if no train is present
-                    let inactive_occupied =
-                        trains.iter().any(|train| train.at_location(inactive_loc));
-                    if !inactive_occupied {
+                    // Only update inactive_loc if no train is present or gliding onto it
+                    if !trains.is_lit(inactive_loc) {
                         update_callback(inactive_loc, 0);
                         update = true;
                     }
 
-                    // Only update active_loc if no train is present
-                    let active_occupied = trains.iter().any(|train| train.at_location(active_loc));
-                    if !active_occupied {
+                    // Only update active_loc if no train is present or gliding onto it
+                    if !trains.is_lit(active_loc) {
                         let brightness = LedPattern::Fade.get_pwm(
-                            self.phase,
+                            phase,
                             settings.switch_brightness() >> 1,
                             settings.switch_brightness(),
                         );
@@ -186,6 +184,17 @@ impl Switch {
         update
     }
 
+    /// Sets the state of the switch in the given direction, no effect if there is no switch in that direction.
+    pub fn set_switched(&mut self, direction: Direction, switched: bool) {
+        let switch_state = match direction {
+            Direction::Anode => &mut self.anode_switched,
+            Direction::Cathode => &mut self.cathode_switched,
+        };
+        if switch_state.is_some() {
+            *switch_state = Some(switched);
+        }
+    }
+
     pub fn is_switched(&self, direction: Direction) -> bool {
         match direction {
             Direction::Anode => self.anode_switched.unwrap_or(false),
@@ -223,19 +232,19 @@ impl Switch {
         }
     }
 
-    // /// Returns the location a train at this switch will go in the given direction if the switch is not switched.
-    // pub fn next_location(&self, direction: Direction) -> Location {
-    //     match direction {
-    //         Direction::Anode => self.anode_next_location,
-    //         Direction::Cathode => self.cathode_next_location,
-    //     }
-    // }
-
-    // /// Returns the location a train at this switch will go in the given direction if the switch is switched.
-    // pub fn fork_location(&self, direction: Direction) -> Option<Location> {
-    //     match direction {
-    //         Direction::Anode => self.anode_fork_location,
-    //         Direction::Cathode => self.cathode_fork_location,
-    //     }
-    // }
+    /// Returns the location a train at this switch will go in the given direction if the switch is not switched.
+    pub fn next_location(&self, direction: Direction) -> Location {
+        match direction {
+            Direction::Anode => self.anode_next_location,
+            Direction::Cathode => self.cathode_next_location,
+        }
+    }
+
+    /// Returns the location a train at this switch will go in the given direction if the switch is switched.
+    pub fn fork_location(&self, direction: Direction) -> Option<Location> {
+        match direction {
+            Direction::Anode => self.anode_fork_location,
+            Direction::Cathode => self.cathode_fork_location,
+        }
+    }
 }
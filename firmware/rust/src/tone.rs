@@ -3,6 +3,7 @@ use atmega_hal::{
     port::{mode::Output, Dynamic, Pin},
 };
 use core::cell::RefCell;
+use light_rail::board::Buzzer;
 
 type Timer = atmega_hal::pac::TC3;
 type Prescalar = avr_device::atmega32u4::tc3::tccr3b::CS3_A;
@@ -45,8 +46,10 @@ impl TimerTone {
 
         Self {}
     }
+}
 
-    pub fn tone(&mut self, frequency: u16, duration: u16) {
+impl Buzzer for TimerTone {
+    fn tone(&mut self, frequency: u16, duration: u16) {
         if frequency == 0 {
             self.no_tone();
             return;
@@ -83,7 +86,7 @@ impl TimerTone {
         });
     }
 
-    pub fn no_tone(&mut self) {
+    fn no_tone(&mut self) {
         avr_device::interrupt::free(|cs| {
             let state_opt_refcell = TONE_STATE.borrow(cs);
             let mut state_opt = state_opt_refcell.borrow_mut();
@@ -1,9 +1,12 @@
+use heapless::Vec;
 use random_trait::Random;
 
 use crate::{
     cargo::*,
     game_settings::GameSettings,
+    game_state::{MAX_CARS, MAX_TRAINS},
     location::{Direction, Location},
+    occupancy::Occupancy,
     random::Rand,
     switch::Switch,
 };
@@ -11,6 +14,59 @@ use crate::{
 pub const DEFAULT_SPEED: u8 = 10;
 const MIN_SPEED: u8 = 0;
 const MAX_SPEED: u8 = 100;
+const ACCELERATION: u16 = 160; // speed gained per tick by a train of mass 1, 1/256 units
+const BRAKING: u16 = 480; // speed lost per tick by a train of mass 1, 1/256 units
+const STATION_SPEED: u8 = 5; // speed a train brakes to when a station it stops at is ahead
+const TRAIL_LEN: usize = 4; // LEDs the caboose left that are still fading out
+const TRAIL_CAPACITY: usize = TRAIL_LEN + 1; // room for the lead left to go dark by reverse
+
+/// How the cars of a train are drawn
+#[derive(Clone, Copy, PartialEq)]
+pub enum TrainStyle {
+    Classic,   // cars drawn by their cargo
+    Headlight, // engine at full brightness leading dimmer cars
+    Gradient,  // cars fading toward the caboose
+    Comet,     // gradient leaving a trail fading out on the LEDs the caboose left
+}
+
+pub const NUM_TRAIN_STYLES: u8 = 4;
+
+impl TrainStyle {
+    /// Returns the style for a settings level, levels past the last style are classic
+    pub fn from_level(level: u8) -> Self {
+        match level {
+            1 => TrainStyle::Headlight,
+            2 => TrainStyle::Gradient,
+            3 => TrainStyle::Comet,
+            _ => TrainStyle::Classic,
+        }
+    }
+
+    /// Returns the brightness of the car at car_index from the engine
+    fn car_brightness(
+        &self,
+        car: &Car,
+        car_index: usize,
+        num_cars: usize,
+        phase: u8,
+        max: u8,
+    ) -> u8 {
+        match self {
+            TrainStyle::Classic => car.cargo.car_brightness(phase, max),
+            TrainStyle::Headlight if car_index == 0 => max,
+            TrainStyle::Headlight => car.cargo.car_brightness(phase, max >> 1),
+            TrainStyle::Gradient | TrainStyle::Comet => {
+                // from full at the engine down to a quarter at the caboose
+                let scale = 256 - (car_index * 192 / num_cars) as u16;
+                ((car.cargo.car_brightness(phase, max) as u16 * scale) >> 8) as u8
+            }
+        }
+    }
+
+    fn has_trail(&self) -> bool {
+        *self == TrainStyle::Comet
+    }
+}
 
 #[derive(Clone, Copy, Default)]
 pub struct Car {
@@ -19,180 +75,612 @@ pub struct Car {
     pub last_brightness: u8,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum CollisionKind {
+    HeadOn,        // engine ran into the engine of another train
+    RearEnd,       // engine ran into a car behind the engine of another train
+    SelfCollision, // engine ran into one of its own cars
+}
+
+/// A train engine that moved onto a location occupied by a car
+#[derive(Clone, Copy)]
+pub struct Collision {
+    pub kind: CollisionKind,
+    pub train_index: usize, // train whose engine moved into the car
+    pub other_index: usize, // train that was hit, same as train_index for self collisions
+    pub location: Location,
+}
+
+impl Collision {
+    /// Returns true if the collision is between the two trains, in either order
+    pub fn involves(&self, train_index: usize, other_index: usize) -> bool {
+        (self.train_index == train_index && self.other_index == other_index)
+            || (self.train_index == other_index && self.other_index == train_index)
+    }
+}
+
+/// Train state, the cars of the train are stored in the car pool of Trains
 pub struct Train {
     direction: Direction,
     speed: u8,
+    target_speed: u8,   // speed the train accelerates or brakes to
+    speed_fraction: u8, // speed change accumulated toward the next unit, 1/256 units
     speed_counter: u8,
-    cars_ptr: *mut Car,
+    station_speed: Option<u8>, // speed resumed after a station stop, while approaching or dwelling at one
+    dwell_ticks: u8,           // ticks left waiting at a station
+    first_car: u8,             // index of the engine in the car pool
     num_cars: u8,
     max_cars: u8,
     last_loc: Location,
-    phase: u8, // phase of the train, used for PWM
+    lead: Option<(Location, u8)>, // LED ahead of the engine and its brightness, lit with smooth motion
+    trail: Vec<(Location, u8), TRAIL_CAPACITY>, // LEDs the caboose left and their brightness, newest first
+    phase: u16, // fixed-point phase of the train, high byte used for PWM
 }
 
 impl Train {
-    pub fn new(
-        cars_ptr: *mut Car,
-        max_cars: u8,
-        loc: Location,
-        cargo: Cargo,
-        speed: Option<u8>,
-    ) -> Self {
-        let mut new_self = Self {
+    fn new(first_car: u8, max_cars: u8, loc: Location, speed: Option<u8>) -> Self {
+        Self {
             direction: if Rand::default().get_bool() {
                 Direction::Anode
             } else {
                 Direction::Cathode
             },
             speed: speed.unwrap_or(DEFAULT_SPEED),
+            target_speed: speed.unwrap_or(DEFAULT_SPEED),
+            speed_fraction: 0,
             speed_counter: 0,
-            cars_ptr,
+            station_speed: None,
+            dwell_ticks: 0,
+            first_car,
             num_cars: 0,
             max_cars,
             last_loc: loc,
-            phase: Rand::default().get_u8(), // initial phase
-        };
+            lead: None,
+            trail: Vec::new(),
+            phase: Rand::default().get_u16(), // initial phase
+        }
+    }
 
-        new_self.add_car(cargo);
-        new_self
+    /// Returns the number of cars in the train
+    pub fn len(&self) -> usize {
+        self.num_cars as usize
     }
 
-    pub fn add_car(&mut self, cargo: Cargo) -> Option<Location> {
-        if self.num_cars >= self.max_cars {
-            return None;
+    /// Returns the max number of cars the train can grow to
+    pub fn max_cars(&self) -> usize {
+        self.max_cars as usize
+    }
+
+    /// Returns the direction the engine moves from its location
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns speed of the train
+    pub fn speed(&self) -> u8 {
+        self.speed
+    }
+
+    /// Sets the speed of the train at once, clamping it between MIN_SPEED and MAX_SPEED.
+    /// Ends any station stop.
+    pub fn set_speed(&mut self, speed: u8) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+        self.target_speed = self.speed;
+        self.speed_fraction = 0;
+        self.speed_counter = 0;
+        self.station_speed = None;
+        self.dwell_ticks = 0;
+    }
+
+    /// Returns the speed the train accelerates or brakes to, or departs at while dwelling at a station
+    pub fn target_speed(&self) -> u8 {
+        match self.station_speed {
+            Some(speed) if self.is_dwelling() => speed,
+            _ => self.target_speed,
         }
+    }
 
-        let loc = if self.num_cars == 0 {
-            self.last_loc
+    /// Sets the speed the train accelerates or brakes to, clamping it between MIN_SPEED and MAX_SPEED.
+    /// Heavier trains, with more cars and more of them loaded, take longer to reach it.
+    /// While dwelling at a station the train stays put and departs at this speed instead.
+    pub fn set_target_speed(&mut self, speed: u8) {
+        let speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+        if self.is_dwelling() {
+            self.station_speed = Some(speed);
         } else {
-            let caboose_loc = self.caboose().loc;
-            let inv_caboose_dir = if self.num_cars > 1 {
-                let next_car_loc = self.cars()[self.num_cars as usize - 2].loc;
-                if caboose_loc
-                    .next(Direction::Anode, Rand::default().get_bool())
-                    .0
-                    == next_car_loc
-                {
-                    Direction::Cathode
-                } else {
-                    Direction::Anode
-                }
-            } else {
-                self.direction
-            };
-            caboose_loc.next(inv_caboose_dir, false).0 // TODO: is switched
+            self.target_speed = speed;
+        }
+    }
+
+    /// Accelerates or brakes toward the target speed by one tick, rates are divided by the mass of the train
+    fn approach_target_speed(&mut self, mass: u16) {
+        let rate = if self.target_speed > self.speed {
+            ACCELERATION
+        } else {
+            BRAKING
+        } / mass;
+        let change = self.speed_fraction as u16 + rate.max(1);
+        self.speed_fraction = change as u8;
+        let step = (change >> 8) as u8;
+
+        self.speed = if self.target_speed > self.speed {
+            self.speed.saturating_add(step).min(self.target_speed)
+        } else {
+            self.speed.saturating_sub(step).max(self.target_speed)
         };
+        if self.speed == self.target_speed {
+            self.speed_fraction = 0;
+        }
+    }
 
-        self.num_cars += 1;
-        let new_car = self.cars_mut().last_mut().unwrap();
-        new_car.loc = loc;
-        new_car.cargo = cargo;
+    /// Returns true while the train waits at a station
+    pub fn is_dwelling(&self) -> bool {
+        self.dwell_ticks > 0
+    }
 
-        Some(loc)
+    /// Brakes to STATION_SPEED for a station ahead, the speed before is resumed when the train departs
+    pub fn approach_station(&mut self) {
+        self.station_speed.get_or_insert(self.target_speed);
+        self.target_speed = self.target_speed.min(STATION_SPEED);
     }
 
-    pub fn remove_car(&mut self) -> Option<Location> {
-        if self.num_cars <= 1 {
-            return None;
+    /// Stops the train at a station, it departs after dwell_ticks at the speed it had before approaching
+    pub fn stop_at_station(&mut self, dwell_ticks: u8) {
+        let station_speed = self.station_speed.unwrap_or(self.target_speed);
+        self.set_speed(0);
+        self.station_speed = Some(station_speed);
+        self.dwell_ticks = dwell_ticks.max(1);
+    }
+
+    /// Ends a station stop, resuming the speed from before unless the train was sped up since
+    pub fn depart(&mut self) {
+        self.dwell_ticks = 0;
+        if let Some(speed) = self.station_speed.take() {
+            if self.target_speed <= STATION_SPEED {
+                self.set_target_speed(speed);
+            }
         }
+    }
 
-        let loc = self.caboose().loc;
-        self.num_cars -= 1;
-        Some(loc)
+    /// Returns the previous location of the caboose before the last move
+    pub fn last_loc(&self) -> Location {
+        self.last_loc
     }
 
-    /// Unsafe function if not called properly, should only be called when first train is initialized
-    pub fn init_cars(&mut self, cargo: Cargo, num_cars: u8, max_cars: u8) {
-        self.num_cars = num_cars;
-        self.max_cars = max_cars;
+    /// Returns the LED lit ahead of the engine with smooth motion and its brightness
+    pub fn lead(&self) -> Option<(Location, u8)> {
+        self.lead
+    }
 
-        for car in self.cars_mut().iter_mut() {
-            car.cargo = cargo;
-            car.last_brightness = 0;
+    /// Returns the LEDs the caboose left that are still fading out and their brightness
+    pub fn trail(&self) -> &[(Location, u8)] {
+        &self.trail
+    }
+
+    #[inline(always)]
+    fn car_range(&self) -> core::ops::Range<usize> {
+        self.first_car as usize..self.first_car as usize + self.num_cars as usize
+    }
+}
+
+/// Trains and the pool their cars are allocated from.
+/// Each train owns a contiguous range of the pool, and ranges are packed in train order so a train can
+/// grow or shrink by shifting the cars of the trains after it. Car access goes through TrainRef and
+/// TrainMut views, so cars can only be changed through a unique borrow of the pool.
+/// The occupancy map is updated along with every car move, so location lookups don't scan the pool.
+pub struct Trains {
+    trains: Vec<Train, MAX_TRAINS>,
+    cars: [Car; MAX_CARS],
+    occupancy: Occupancy,
+}
+
+impl Default for Trains {
+    fn default() -> Self {
+        Self {
+            trains: Vec::new(),
+            cars: [Car::default(); MAX_CARS],
+            occupancy: Occupancy::default(),
         }
     }
+}
 
-    /// Game tick for train, returns location updates as cars move along track
-    pub fn advance<F>(
+impl Trains {
+    pub fn len(&self) -> usize {
+        self.trains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trains.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.trains.is_full()
+    }
+
+    /// Returns the number of cars allocated to all trains
+    pub fn num_cars(&self) -> usize {
+        self.trains.last().map_or(0, |train| train.car_range().end)
+    }
+
+    pub fn get(&self, index: usize) -> Option<TrainRef<'_>> {
+        let train = self.trains.get(index)?;
+        Some(TrainRef {
+            train,
+            index,
+            cars: &self.cars[train.car_range()],
+            occupancy: &self.occupancy,
+        })
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<TrainMut<'_>> {
+        if index < self.trains.len() {
+            Some(TrainMut {
+                trains: self,
+                index,
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = TrainRef<'_>> {
+        (0..self.trains.len()).filter_map(|index| self.get(index))
+    }
+
+    pub fn occupancy(&self) -> &Occupancy {
+        &self.occupancy
+    }
+
+    /// Returns bool if any car of any train is at the given location
+    pub fn at_location(&self, loc: Location) -> bool {
+        self.occupancy.is_occupied(loc)
+    }
+
+    /// Returns bool if any car is at the given location, an engine is gliding onto it, or a trail fades out on it
+    pub fn is_lit(&self, loc: Location) -> bool {
+        self.at_location(loc)
+            || self.trains.iter().any(|train| {
+                matches!(train.lead, Some((lead_loc, _)) if lead_loc == loc)
+                    || train.trail.iter().any(|&(trail_loc, _)| trail_loc == loc)
+            })
+    }
+
+    /// Returns the index of the train at the given location
+    pub fn train_at(&self, loc: Location) -> Option<usize> {
+        self.occupancy.owner(loc)
+    }
+
+    /// Returns the collision of the train's engine with its own cars or another train, if any.
+    /// Engines that swapped locations in the same tick passed through each other, a head-on collision
+    /// that leaves no location shared.
+    pub fn collision(&self, train_index: usize) -> Option<Collision> {
+        let train = self.get(train_index)?;
+        let location = train.front();
+        let is_shared = self.occupancy.is_shared(location);
+
+        let collision = |kind, other_index| Collision {
+            kind,
+            train_index,
+            other_index,
+            location,
+        };
+
+        if is_shared && train.cars()[1..].iter().any(|car| car.loc == location) {
+            return Some(collision(CollisionKind::SelfCollision, train_index));
+        }
+
+        for (other_index, other_train) in self.iter().enumerate() {
+            if other_index == train_index {
+                continue;
+            }
+            if other_train.engine_left() == location && train.engine_left() == other_train.front() {
+                return Some(collision(CollisionKind::HeadOn, other_index));
+            }
+            if !is_shared || !other_train.at_location(location) {
+                continue;
+            }
+            let kind = if other_train.front() == location {
+                CollisionKind::HeadOn
+            } else {
+                CollisionKind::RearEnd
+            };
+            return Some(collision(kind, other_index));
+        }
+        None
+    }
+
+    /// Adds a train with a single car at the given location, returns its index or None if there is no room
+    pub fn push(
         &mut self,
-        settings: &GameSettings,
-        switches: &[Switch],
-        mut update_callback: F,
-        force_update: bool,
-    ) -> bool
-    where
-        F: FnMut(Location, u8),
-    {
-        self.phase = self.phase.wrapping_add(1);
-        self.speed_counter += self.speed;
-
-        // If not enough speed accumulated, just update brightness and return
-        if self.speed_counter < MAX_SPEED {
-            for car in self.cars_mut().iter_mut() {
-                let brightness = car
-                    .cargo
-                    .car_brightness(self.phase, settings.car_brightness());
-                if force_update || car.last_brightness != brightness {
-                    car.last_brightness = brightness;
-                    update_callback(car.loc, brightness);
-                }
+        loc: Location,
+        cargo: Cargo,
+        max_cars: u8,
+        speed: Option<u8>,
+    ) -> Option<usize> {
+        let first_car = self.num_cars();
+        if self.trains.is_full() || first_car >= MAX_CARS {
+            return None;
+        }
+
+        let index = self.trains.len();
+        self.trains
+            .push(Train::new(first_car as u8, max_cars, loc, speed))
+            .ok();
+        self.get_mut(index)?.add_car(cargo);
+        Some(index)
+    }
+
+    /// Removes the train and compacts the car pool
+    pub fn remove(&mut self, index: usize) {
+        if index >= self.trains.len() {
+            return;
+        }
+
+        let num_cars = self.num_cars();
+        let range = self.trains[index].car_range();
+        self.cars.copy_within(range.end..num_cars, range.start);
+        for train in self.trains[index + 1..].iter_mut() {
+            train.first_car -= range.len() as u8;
+        }
+        self.trains.remove(index);
+
+        // owners of the following trains shifted, rare enough to rebuild the whole map
+        self.occupancy.clear();
+        for train_index in 0..self.trains.len() {
+            for car_index in self.trains[train_index].car_range() {
+                self.occupancy.enter(self.cars[car_index].loc, train_index);
             }
+        }
+    }
+
+    pub fn pop(&mut self) {
+        if !self.trains.is_empty() {
+            self.remove(self.trains.len() - 1);
+        }
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        while self.trains.len() > len {
+            self.pop();
+        }
+    }
+
+    /// Inserts a car at the end of the train, shifting the cars of the following trains
+    fn insert_car(&mut self, index: usize, car: Car) -> bool {
+        let num_cars = self.num_cars();
+        let train = &self.trains[index];
+        if num_cars >= MAX_CARS || train.num_cars >= train.max_cars {
             return false;
         }
 
-        self.speed_counter -= MAX_SPEED;
+        let end = train.car_range().end;
+        self.cars.copy_within(end..num_cars, end + 1);
+        self.cars[end] = car;
+        self.trains[index].num_cars += 1;
+        for train in self.trains[index + 1..].iter_mut() {
+            train.first_car += 1;
+        }
+        self.occupancy.enter(car.loc, index);
+        true
+    }
 
-        // Move train cars from rear to front, updating locations and brightness
-        self.last_loc = self.caboose().loc;
-        update_callback(self.last_loc, 0);
+    /// Removes the last car of the train, shifting the cars of the following trains
+    fn remove_car(&mut self, index: usize) {
+        let num_cars = self.num_cars();
+        let end = self.trains[index].car_range().end;
+        let loc = self.cars[end - 1].loc;
+        self.cars.copy_within(end..num_cars, end - 1);
+        self.trains[index].num_cars -= 1;
+        for train in self.trains[index + 1..].iter_mut() {
+            train.first_car -= 1;
+        }
+        self.leave(loc);
+    }
 
-        let cars = self.cars_mut();
-        for i in (1..cars.len()).rev() {
-            cars[i].loc = cars[i - 1].loc;
-            let brightness = cars[i]
-                .cargo
-                .car_brightness(self.phase, settings.car_brightness());
-            update_callback(cars[i].loc, brightness);
+    /// Updates the occupancy of a location a car left
+    fn leave(&mut self, loc: Location) {
+        if !self.occupancy.is_shared(loc) {
+            self.occupancy.set(loc, None, false);
+            return;
         }
 
-        // Determine if the front of the train is on a switched track
-        let front_loc = self.front();
-        let is_switched = switches
-            .iter()
-            .any(|switch| front_loc == switch.location() && switch.is_switched(self.direction));
-
-        // Advance the engine to the next location and update brightness
-        let (next_loc, new_dir) = self.front().next(self.direction, is_switched);
-        self.direction = new_dir;
-        self.engine_mut().loc = next_loc;
-        let brightness = self
-            .engine()
-            .cargo
-            .car_brightness(self.phase, settings.car_brightness());
-        update_callback(self.front(), brightness);
+        // more than one car was here, find who is left
+        let mut owner = None;
+        let mut count = 0;
+        for (train_index, train) in self.iter().enumerate() {
+            let cars_here = train.cars().iter().filter(|car| car.loc == loc).count();
+            if cars_here > 0 {
+                owner.get_or_insert(train_index);
+                count += cars_here;
+            }
+        }
+        self.occupancy.set(loc, owner, count > 1);
+    }
+}
 
-        true
+impl core::ops::Index<usize> for Trains {
+    type Output = Train;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.trains[index]
     }
+}
 
-    /// Returns the vector of cars in the train
-    pub fn cars(&self) -> &[Car] {
-        unsafe { core::slice::from_raw_parts(self.cars_ptr, self.num_cars as usize) }
+impl core::ops::IndexMut<usize> for Trains {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.trains[index]
     }
+}
 
-    /// Returns the number of cars in the train
-    pub fn len(&self) -> usize {
-        self.num_cars as usize
+/// Shared view of a train and its cars
+#[derive(Clone, Copy)]
+pub struct TrainRef<'a> {
+    train: &'a Train,
+    index: usize,
+    cars: &'a [Car],
+    occupancy: &'a Occupancy,
+}
+
+impl<'a> TrainRef<'a> {
+    /// Returns the cars in the train, engine first
+    pub fn cars(&self) -> &'a [Car] {
+        self.cars
     }
 
-    /// Returns speed of the train
-    pub fn speed(&self) -> u8 {
-        self.speed
+    /// Returns reference to the first car of the train (engine)
+    pub fn engine(&self) -> &'a Car {
+        self.cars.first().unwrap()
     }
 
-    /// Sets the speed of the train, clamping it between MIN_SPEED and MAX_SPEED
-    pub fn set_speed(&mut self, speed: u8) {
-        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
-        self.speed_counter = 0;
+    /// Returns the current location of the train engine
+    pub fn front(&self) -> Location {
+        self.engine().loc
+    }
+
+    /// Returns reference to the last car of the train (caboose)
+    pub fn caboose(&self) -> &'a Car {
+        self.cars.last().unwrap()
+    }
+
+    /// Returns the location the engine left on its last move, where the car behind it is now
+    fn engine_left(&self) -> Location {
+        self.cars.get(1).map_or(self.last_loc, |car| car.loc)
+    }
+
+    /// Returns bool if any car is at the given location
+    pub fn at_location(&self, loc: Location) -> bool {
+        if self.occupancy.is_shared(loc) {
+            self.cars.iter().any(|car| car.loc == loc)
+        } else {
+            self.occupancy.owner(loc) == Some(self.index)
+        }
+    }
+}
+
+impl core::ops::Deref for TrainRef<'_> {
+    type Target = Train;
+
+    fn deref(&self) -> &Self::Target {
+        self.train
+    }
+}
+
+impl core::ops::Index<usize> for TrainRef<'_> {
+    type Output = Car;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.cars[index]
+    }
+}
+
+/// Unique view of a train, borrows the whole pool since growing a train moves the cars of other trains
+pub struct TrainMut<'a> {
+    trains: &'a mut Trains,
+    index: usize,
+}
+
+impl TrainMut<'_> {
+    /// Returns a shared view of the train for reading its cars
+    pub fn view(&self) -> TrainRef<'_> {
+        self.trains.get(self.index).unwrap()
+    }
+
+    /// Returns the current location of the train engine
+    pub fn front(&self) -> Location {
+        self.view().front()
+    }
+
+    /// Returns bool if any car is at the given location
+    pub fn at_location(&self, loc: Location) -> bool {
+        self.view().at_location(loc)
+    }
+
+    pub fn add_car(&mut self, cargo: Cargo) -> Option<Location> {
+        let loc = {
+            let train = self.view();
+            if train.num_cars == 0 {
+                train.last_loc
+            } else {
+                let caboose_loc = train.caboose().loc;
+                let inv_caboose_dir = if train.num_cars > 1 {
+                    // away from the car ahead of the caboose
+                    let next_car_loc = train[train.len() - 2].loc;
+                    caboose_loc
+                        .direction_to(next_car_loc)
+                        .map_or(Direction::Anode, Direction::opposite)
+                } else {
+                    // second car goes behind the engine
+                    train.direction.opposite()
+                };
+                // both ways of a fork are adjacent to the caboose, the car goes on the straight one
+                caboose_loc.next(inv_caboose_dir, false).0
+            }
+        };
+
+        let new_car = Car {
+            loc,
+            cargo,
+            last_brightness: 0,
+        };
+        if self.trains.insert_car(self.index, new_car) {
+            Some(loc)
+        } else {
+            None
+        }
+    }
+
+    pub fn remove_car(&mut self) -> Option<Location> {
+        let train = self.view();
+        if train.num_cars <= 1 {
+            return None;
+        }
+
+        let loc = train.caboose().loc;
+        self.trains.remove_car(self.index);
+        Some(loc)
+    }
+
+    /// Turns the train around, the caboose becomes the engine and leads the train back along its cars.
+    /// The train stops partway between LEDs, so it starts the other way from whole LEDs.
+    pub fn reverse(&mut self) {
+        let (train, cars) = self.split_mut();
+        // the location ahead of the engine is behind the caboose once turned around
+        let ahead_loc = cars[0].loc.next(train.direction, false).0;
+        cars.reverse();
+
+        // a lone engine heads back toward the location it left, switches route it from there as usual,
+        // the new engine of a longer train moves away from its cars
+        train.direction = match cars.get(1) {
+            Some(behind) => cars[0]
+                .loc
+                .direction_to(behind.loc)
+                .map(Direction::opposite),
+            None => cars[0].loc.direction_to(train.last_loc),
+        }
+        .unwrap_or(train.direction.opposite());
+        train.last_loc = ahead_loc;
+        train.speed_counter = 0;
+
+        // the lead and trail are on the old sides, they go dark on the next advance and are rebuilt
+        if let Some((lead_loc, _)) = train.lead.take() {
+            train.trail.push((lead_loc, 0)).ok();
+        }
+        for (_, brightness) in train.trail.iter_mut() {
+            *brightness = 0;
+        }
+    }
+
+    /// Resizes the train to num_cars and resets the cargo of all cars, keeping the cars that are already
+    /// on the track for a smooth transition between modes
+    pub fn init_cars(&mut self, cargo: Cargo, num_cars: u8, max_cars: u8) {
+        self.trains.trains[self.index].max_cars = max_cars;
+        while self.len() > num_cars as usize && self.remove_car().is_some() {}
+        while self.len() < num_cars as usize && self.add_car(cargo).is_some() {}
+
+        for car in self.cars_mut().iter_mut() {
+            car.cargo = cargo;
+            car.last_brightness = 0;
+        }
     }
 
     /// Adds cargo to train, returns true if train loads cargo into an available empty car
@@ -218,53 +706,186 @@ impl Train {
         false
     }
 
-    /// Returns reference to the first car of the train (engine)
-    pub fn engine(&self) -> &Car {
-        self.cars().first().unwrap()
-    }
+    /// Game tick for train, returns location updates as cars move along track.
+    /// With smooth motion each car cross-fades from its LED onto the next one as its step progresses, the engine
+    /// onto the LED ahead and the caboose out of the LED it is about to leave.
+    pub fn advance<F>(
+        &mut self,
+        settings: &GameSettings,
+        switches: &[Switch],
+        style: TrainStyle,
+        mut update_callback: F,
+        force_update: bool,
+    ) -> bool
+    where
+        F: FnMut(Location, u8),
+    {
+        let (train, cars) = self.split_mut();
+        train.phase = settings.advance_phase(train.phase, 1);
+        if train.dwell_ticks > 0 {
+            train.dwell_ticks -= 1;
+            if train.dwell_ticks == 0 {
+                train.depart();
+            }
+        }
+        if train.speed != train.target_speed {
+            // every car weighs one, and one more when loaded
+            let loaded = cars.iter().filter(|car| car.cargo != Cargo::Empty).count();
+            train.approach_target_speed((cars.len() + loaded) as u16);
+        }
+        // scaled speed is clamped so the counter can't overflow
+        train.speed_counter += settings.scale_speed(train.speed).min(MAX_SPEED);
+        let phase = (train.phase >> 8) as u8;
+
+        let moved = train.speed_counter >= MAX_SPEED;
+        if moved {
+            train.speed_counter -= MAX_SPEED;
+
+            // Move train cars from rear to front
+            train.last_loc = cars.last().unwrap().loc;
+            update_callback(train.last_loc, 0);
+            for i in (1..cars.len()).rev() {
+                cars[i].loc = cars[i - 1].loc;
+            }
 
-    /// Returns the current location of the train engine
-    pub fn front(&self) -> Location {
-        self.engine().loc
-    }
+            // Advance the engine to the next location
+            let (next_loc, new_dir) = Self::next_engine_loc(train, &cars[0], switches);
+            train.direction = new_dir;
+            cars[0].loc = next_loc;
+        }
 
-    /// Returns reference to the last car of the train (caboose)
-    pub fn caboose(&self) -> &Car {
-        self.cars().last().unwrap()
-    }
+        // Update car brightness, every car is drawn on a move as the cars shifted
+        let max_brightness = settings.car_brightness();
+        let progress = if settings.is_smooth_motion() {
+            train.speed_counter as u16
+        } else {
+            0
+        };
+        let num_cars = cars.len();
+        let car_brightness = |cars: &[Car], i: usize| {
+            style.car_brightness(&cars[i], i, num_cars, phase, max_brightness)
+        };
+        let mut trail_brightness = 0;
+        for i in 0..num_cars {
+            let brightness = car_brightness(cars, i);
+            // the car behind glides onto this LED, or the trail the caboose leaves
+            let brightness = if i + 1 < num_cars {
+                blend(brightness, car_brightness(cars, i + 1), progress)
+            } else {
+                if style.has_trail() {
+                    trail_brightness = brightness >> 1;
+                }
+                blend(brightness, trail_brightness, progress)
+            };
+            let car = &mut cars[i];
+            if force_update || moved || car.last_brightness != brightness {
+                car.last_brightness = brightness;
+                update_callback(car.loc, brightness);
+            }
+        }
 
-    /// Returns the previous location of the caboose before the last move
-    pub fn last_loc(&self) -> Location {
-        self.last_loc
+        // LED ahead of the engine the engine glides onto
+        let lead = if progress > 0 {
+            let (lead_loc, _) = Self::next_engine_loc(train, &cars[0], switches);
+            Some((lead_loc, blend(0, car_brightness(cars, 0), progress)))
+        } else {
+            None
+        };
+
+        if moved {
+            // only the engine and caboose locations change, the cars in between shift onto occupied locations
+            let next_loc = cars[0].loc;
+            let last_loc = train.last_loc;
+            self.trains.occupancy.enter(next_loc, self.index);
+            self.trains.leave(last_loc);
+        }
+
+        // Light the LED ahead of the engine unless a car is there, and clear the one it lit before
+        let occupancy = &self.trains.occupancy;
+        let lead = lead.filter(|&(lead_loc, _)| !occupancy.is_occupied(lead_loc));
+        let train = &mut self.trains.trains[self.index];
+        if let Some((last_lead_loc, _)) = train.lead {
+            if lead.map(|(lead_loc, _)| lead_loc) != Some(last_lead_loc)
+                && !occupancy.is_occupied(last_lead_loc)
+            {
+                update_callback(last_lead_loc, 0);
+            }
+        }
+        if let Some((lead_loc, brightness)) = lead {
+            if force_update || moved || train.lead != lead {
+                update_callback(lead_loc, brightness);
+            }
+        }
+        train.lead = lead;
+
+        // Fade out the trail, cars moving back onto it take over their LED
+        let has_trail = style.has_trail();
+        train.trail.retain_mut(|(trail_loc, brightness)| {
+            if occupancy.is_occupied(*trail_loc) {
+                return false;
+            }
+            *brightness = if has_trail {
+                brightness.saturating_sub((*brightness >> 3) + 1)
+            } else {
+                0
+            };
+            update_callback(*trail_loc, *brightness);
+            *brightness > 0
+        });
+        if moved && trail_brightness > 0 && !occupancy.is_occupied(train.last_loc) {
+            if train.trail.len() >= TRAIL_LEN {
+                if let Some((oldest_loc, _)) = train.trail.pop() {
+                    update_callback(oldest_loc, 0);
+                }
+            }
+            train
+                .trail
+                .insert(0, (train.last_loc, trail_brightness))
+                .ok();
+            update_callback(train.last_loc, trail_brightness);
+        }
+
+        moved
     }
 
-    /// Returns bool if any car is at the given location
-    pub fn at_location(&self, loc: Location) -> bool {
-        self.cars().iter().any(|car| car.loc == loc)
+    /// Returns the location and direction the engine moves to next, following the switch it is on
+    fn next_engine_loc(train: &Train, engine: &Car, switches: &[Switch]) -> (Location, Direction) {
+        let is_switched = switches
+            .iter()
+            .any(|switch| engine.loc == switch.location() && switch.is_switched(train.direction));
+        engine.loc.next(train.direction, is_switched)
     }
 
-    // private mutable functions
+    /// Returns the train and its cars, disjoint borrows of the pool
+    fn split_mut(&mut self) -> (&mut Train, &mut [Car]) {
+        let train = &mut self.trains.trains[self.index];
+        let cars = &mut self.trains.cars[train.car_range()];
+        (train, cars)
+    }
 
-    /// Returns mutable reference to the engine (first car of the train)
-    fn engine_mut(&mut self) -> &mut Car {
-        self.cars_mut().first_mut().unwrap()
+    /// Returns the mutable cars of the train
+    fn cars_mut(&mut self) -> &mut [Car] {
+        self.split_mut().1
     }
+}
 
-    // /// Returns mutable reference to the last car of the train (caboose)
-    // fn caboose_mut(&mut self) -> &mut Car {
-    //     self.cars_mut().last_mut().unwrap()
-    // }
+impl core::ops::Deref for TrainMut<'_> {
+    type Target = Train;
 
-    /// Returns the vector of cars in the train
-    fn cars_mut(&self) -> &mut [Car] {
-        unsafe { core::slice::from_raw_parts_mut(self.cars_ptr, self.num_cars as usize) }
+    fn deref(&self) -> &Self::Target {
+        &self.trains.trains[self.index]
     }
 }
 
-impl core::ops::Index<usize> for Train {
-    type Output = Car;
-
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.cars()[index]
+impl core::ops::DerefMut for TrainMut<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.trains.trains[self.index]
     }
 }
+
+/// Cross-fades from the brightness at progress 0 to the next brightness at MAX_SPEED
+#[inline(always)]
+fn blend(brightness: u8, next_brightness: u8, progress: u16) -> u8 {
+    ((brightness as u16 * (MAX_SPEED as u16 - progress) + next_brightness as u16 * progress)
+        / MAX_SPEED as u16) as u8
+}
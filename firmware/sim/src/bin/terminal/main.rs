@@ -0,0 +1,283 @@
+//! Terminal renderer for the Light Rail game core.
+//! Draws every board LED at its physical position and the digit display as seven-segment glyphs,
+//! with keyboard keys mapped to the board buttons.
+//!
+//! Keys: 1-8 switch buttons, arrows/WASD direction buttons, shift+arrows/WASD hold direction,
+//! q/Esc to quit.
+//!
+//! Usage: terminal [seed]
+
+use std::{
+    io::{self, Stdout, Write},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal,
+};
+use light_rail::{
+    clock::{self, Scheduler},
+    game::Game,
+    game_settings::GameSettings,
+    input::{BoardInput, HOLD_CYCLES},
+    location::{Direction, Location, NUM_LOCATION_NODES},
+    random::Rand,
+    sim::{
+        segments::{A, B, C, D, DP, E, F, G},
+        SimButtons, SimBuzzer, SimDigits, SimEeprom, SimLeds,
+    },
+    switch::Switch,
+    NUM_BUTTONS, NUM_DIGITS,
+};
+
+const POLL_PERIOD: Duration = Duration::from_millis(1); // same resolution as the firmware clock
+const FRAME_TICKS: u32 = 3; // render every 3 ticks, ~33 fps
+const PRESS_TICKS: u8 = 8; // a key press holds its button down for this many ticks
+const HOLD_TICKS: u8 = HOLD_CYCLES + 8; // a shifted key press holds its button long enough for a hold event
+const GRID_X: u8 = 3; // half millimeters per terminal column
+const GRID_Y: u8 = 6; // half millimeters per terminal row, terminal cells are about twice as tall as wide
+const BOARD_ROW: u16 = 2; // first terminal row of the board, below the header
+
+// button indices from input.rs
+const BUTTON_UP: usize = 8;
+const BUTTON_DOWN: usize = 9;
+const BUTTON_LEFT: usize = 10;
+const BUTTON_RIGHT: usize = 11;
+
+type SimGame = Game<SimBuzzer, SimDigits, SimButtons, SimLeds, SimEeprom>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum LedKind {
+    Track,
+    Platform,
+    SwitchFork,
+}
+
+/// Restores the terminal when the renderer exits or panics
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new(stdout: &mut Stdout) -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        execute!(stdout, ResetColor, cursor::Show, terminal::LeaveAlternateScreen).ok();
+        terminal::disable_raw_mode().ok();
+    }
+}
+
+fn main() -> io::Result<()> {
+    let seed = std::env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.subsec_nanos())
+                .unwrap_or(0)
+        });
+    Rand::seed(seed);
+
+    let board_eeprom = SimEeprom::default();
+    let settings = GameSettings::new(&board_eeprom);
+    let mut game: SimGame = Game::new(
+        SimBuzzer::default(),
+        SimDigits::default(),
+        BoardInput::new(SimButtons::default()),
+        SimLeds::default(),
+        board_eeprom,
+        settings,
+    );
+    game.restart();
+
+    let led_kinds = led_kinds(&game.state().switches);
+
+    let mut stdout = io::stdout();
+    let _guard = TerminalGuard::new(&mut stdout)?;
+    execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
+
+    // the clock follows real time and game ticks are scheduled from it, like the firmware timer interrupt
+    let start = Instant::now();
+    let mut scheduler = Scheduler::new(clock::TICK_MS);
+    let mut button_ticks = [0u8; NUM_BUTTONS];
+    let mut tick: u32 = 0;
+    loop {
+        let elapsed_ms = start.elapsed().as_millis() as u32;
+        clock::advance(elapsed_ms.wrapping_sub(clock::now()));
+
+        while event::poll(Duration::ZERO)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Release {
+                    continue;
+                }
+                if is_quit_key(&key) {
+                    return Ok(());
+                }
+                if let Some((button, ticks)) = key_to_button(&key) {
+                    button_ticks[button] = button_ticks[button].max(ticks);
+                }
+            }
+        }
+
+        for _ in 0..scheduler.due() {
+            let buttons = game.board_input_mut().buttons_mut();
+            for (pressed, ticks) in buttons.pressed.iter_mut().zip(button_ticks.iter_mut()) {
+                *pressed = *ticks > 0;
+                *ticks = ticks.saturating_sub(1);
+            }
+
+            game.tick();
+
+            if tick % FRAME_TICKS == 0 {
+                render(&mut stdout, &game, &led_kinds, seed, scheduler.overruns())?;
+            }
+            tick = tick.wrapping_add(1);
+        }
+
+        thread::sleep(POLL_PERIOD);
+    }
+}
+
+fn led_kinds(switches: &[Switch]) -> [LedKind; NUM_LOCATION_NODES] {
+    let mut kinds = [LedKind::Track; NUM_LOCATION_NODES];
+    for platform_loc in Location::platform_locs() {
+        kinds[platform_loc.index() as usize] = LedKind::Platform;
+    }
+    for switch in switches {
+        for dir in [Direction::Anode, Direction::Cathode] {
+            if let Some(fork_loc) = switch.fork_location(dir) {
+                kinds[fork_loc.index() as usize] = LedKind::SwitchFork;
+                kinds[switch.next_location(dir).index() as usize] = LedKind::SwitchFork;
+            }
+        }
+    }
+    kinds
+}
+
+fn is_quit_key(key: &KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Esc | KeyCode::Char('q'))
+        || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
+/// Maps a key to a button index and how many ticks it stays pressed
+fn key_to_button(key: &KeyEvent) -> Option<(usize, u8)> {
+    let shifted = key.modifiers.contains(KeyModifiers::SHIFT);
+    let (button, held) = match key.code {
+        KeyCode::Char(c @ '1'..='8') => ((c as u8 - b'1') as usize, false),
+        KeyCode::Up => (BUTTON_UP, shifted),
+        KeyCode::Down => (BUTTON_DOWN, shifted),
+        KeyCode::Left => (BUTTON_LEFT, shifted),
+        KeyCode::Right => (BUTTON_RIGHT, shifted),
+        KeyCode::Char(c) => match c {
+            'w' | 'W' => (BUTTON_UP, c.is_ascii_uppercase()),
+            's' | 'S' => (BUTTON_DOWN, c.is_ascii_uppercase()),
+            'a' | 'A' => (BUTTON_LEFT, c.is_ascii_uppercase()),
+            'd' | 'D' => (BUTTON_RIGHT, c.is_ascii_uppercase()),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some((button, if held { HOLD_TICKS } else { PRESS_TICKS }))
+}
+
+fn led_style(kind: LedKind, brightness: u8) -> (char, Color, Color) {
+    let glyph = match brightness {
+        0 => '·',
+        1..=31 => '+',
+        32..=127 => 'o',
+        _ => '@',
+    };
+    let foreground = match (kind, brightness) {
+        (_, 0) => Color::DarkGrey,
+        (LedKind::Platform, 1..=127) => Color::DarkRed,
+        (LedKind::Platform, _) => Color::Red,
+        (_, 1..=127) => Color::DarkYellow,
+        (_, _) => Color::Yellow,
+    };
+    let background = match kind {
+        LedKind::SwitchFork => Color::DarkBlue,
+        _ => Color::Reset,
+    };
+    (glyph, foreground, background)
+}
+
+/// Returns the 3 rows of a seven-segment glyph for the given segment data
+fn digit_glyph(segments: u8) -> [[char; 4]; 3] {
+    let on = |segment: u8, c: char| if segments & segment != 0 { c } else { ' ' };
+    [
+        [' ', on(A, '_'), ' ', ' '],
+        [on(F, '|'), on(G, '_'), on(B, '|'), ' '],
+        [on(E, '|'), on(D, '_'), on(C, '|'), on(DP, '.')],
+    ]
+}
+
+fn render(
+    stdout: &mut Stdout,
+    game: &SimGame,
+    led_kinds: &[LedKind; NUM_LOCATION_NODES],
+    seed: u32,
+    overruns: u32,
+) -> io::Result<()> {
+    queue!(
+        stdout,
+        cursor::MoveTo(0, 0),
+        ResetColor,
+        Print(format!(
+            "Light Rail  mode {}  seed {}  overruns {}  [1-8] switches [arrows/wasd] buttons [shift] hold [q] quit",
+            game.state().target_mode_index,
+            seed,
+            overruns
+        )),
+        terminal::Clear(terminal::ClearType::UntilNewLine)
+    )?;
+
+    // board LEDs at their physical positions
+    let pixels = &game.board_leds().pixels;
+    let mut board_rows = 0;
+    for index in 0..NUM_LOCATION_NODES {
+        let (x, y) = Location::new(index as u8).position();
+        let column = ((x + GRID_X / 2) / GRID_X) as u16;
+        let row = ((y + GRID_Y / 2) / GRID_Y) as u16;
+        board_rows = board_rows.max(row + 1);
+
+        let (glyph, foreground, background) = led_style(led_kinds[index], pixels[index]);
+        queue!(
+            stdout,
+            cursor::MoveTo(column, BOARD_ROW + row),
+            SetForegroundColor(foreground),
+            SetBackgroundColor(background),
+            Print(glyph)
+        )?;
+    }
+    queue!(stdout, ResetColor)?;
+
+    // digit display below the board
+    let digits = game.board_digits();
+    let digits_row = BOARD_ROW + board_rows + 1;
+    for line in 0..3 {
+        let mut text = String::new();
+        for digit in 0..NUM_DIGITS as usize {
+            text.extend(digit_glyph(digits.segments[digit])[line]);
+        }
+        queue!(
+            stdout,
+            cursor::MoveTo(0, digits_row + line as u16),
+            SetForegroundColor(Color::Red),
+            Print(text)
+        )?;
+    }
+    queue!(stdout, ResetColor)?;
+
+    stdout.flush()
+}
@@ -0,0 +1,75 @@
+//! Headless host runner for the Light Rail game core.
+//! Runs `Game::tick` against in-memory board fakes and prints the digit display as it changes.
+//!
+//! Usage: light-rail-sim [ticks] [mode index] [seed]
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use light_rail::{
+    clock,
+    game::Game,
+    game_settings::GameSettings,
+    game_state::DisplayState,
+    input::BoardInput,
+    random::Rand,
+    sim::{SimButtons, SimBuzzer, SimDigits, SimEeprom, SimLeds},
+};
+
+const DEFAULT_TICKS: u32 = 1000;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let ticks = args
+        .next()
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(DEFAULT_TICKS);
+    let mode_index = args.next().and_then(|arg| arg.parse().ok()).unwrap_or(0);
+    let seed = args.next().and_then(|arg| arg.parse().ok()).unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0)
+    });
+    Rand::seed(seed);
+
+    let board_eeprom = SimEeprom::default();
+    let settings = GameSettings::new(&board_eeprom);
+    let mut game = Game::new(
+        SimBuzzer::default(),
+        SimDigits::default(),
+        BoardInput::new(SimButtons::default()),
+        SimLeds::default(),
+        board_eeprom,
+        settings,
+    );
+    game.restart();
+    game.state_mut().target_mode_index = mode_index;
+
+    let mut last_display = DisplayState::None;
+    // the clock runs in simulated time, a tick period per tick
+    for tick in 0..ticks {
+        clock::advance(clock::TICK_MS);
+        game.tick();
+
+        let display = game.state().display;
+        if display != last_display {
+            last_display = display;
+            println!("{:>6}: {}", tick, display_string(display));
+        }
+    }
+
+    let lit = game.board_leds().pixels.iter().filter(|&&b| b > 0).count();
+    println!("seed {}, {} ticks, {} LEDs lit", seed, ticks, lit);
+}
+
+fn display_string(display: DisplayState) -> String {
+    match display {
+        DisplayState::None => String::from("---"),
+        DisplayState::Score(score) => format!("{:>3}", score),
+        DisplayState::Text(text) => String::from_utf8_lossy(&text).into_owned(),
+        DisplayState::Segments(segments) => format!(
+            "[{:02x} {:02x} {:02x}]",
+            segments[0], segments[1], segments[2]
+        ),
+    }
+}
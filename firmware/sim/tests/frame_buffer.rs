@@ -0,0 +1,76 @@
+//! Entity and effect layers of the frame buffer.
+
+use light_rail::{board::LedSink, frame_buffer::FrameBuffer, location::NUM_LOCATION_NODES};
+
+/// Sink keeping the written pixels and blink bits, blinking in hardware
+struct BlinkLeds {
+    pixels: [u8; NUM_LOCATION_NODES],
+    blink: Vec<u8>,
+}
+
+impl Default for BlinkLeds {
+    fn default() -> Self {
+        Self {
+            pixels: [0; NUM_LOCATION_NODES],
+            blink: Vec::new(),
+        }
+    }
+}
+
+impl LedSink for BlinkLeds {
+    const HARDWARE_BLINK: bool = true;
+
+    fn clear(&mut self) {
+        self.pixels = [0; NUM_LOCATION_NODES];
+    }
+
+    fn set_pixel(&mut self, index: u8, brightness: u8) {
+        self.pixels[index as usize] = brightness;
+    }
+
+    fn set_blink(&mut self, mask: &[u8]) {
+        self.blink = mask.to_vec();
+    }
+}
+
+#[test]
+fn leds_show_the_brighter_layer() {
+    let mut frame = FrameBuffer::default();
+    let mut leds = BlinkLeds::default();
+    frame.set_pixel(3, 100);
+    frame.set_effect_pixel(3, 50);
+    frame.set_effect_pixel(4, 50);
+    frame.flush(&mut leds);
+    assert_eq!(leds.pixels[3], 100);
+    assert_eq!(leds.pixels[4], 50);
+
+    // entities keep drawing under the effect
+    frame.set_pixel(4, 20);
+    frame.set_pixel(5, 80);
+    frame.flush(&mut leds);
+    assert_eq!(leds.pixels[4], 50);
+    assert_eq!(leds.pixels[5], 80);
+
+    frame.clear_effect();
+    frame.flush(&mut leds);
+    assert_eq!(&leds.pixels[3..=5], &[100, 20, 80]);
+}
+
+#[test]
+fn blinking_pixels_hold_steady_under_a_brighter_effect() {
+    let mut frame = FrameBuffer::default();
+    let mut leds = BlinkLeds::default();
+    frame.set_pixel_blink(9, 40, true);
+    frame.flush(&mut leds);
+    assert_eq!(leds.blink[1], 1 << 1);
+
+    frame.set_effect_pixel(9, 80);
+    frame.flush(&mut leds);
+    assert_eq!(leds.blink[1], 0);
+    assert_eq!(leds.pixels[9], 80);
+
+    frame.clear_effect();
+    frame.flush(&mut leds);
+    assert_eq!(leds.blink[1], 1 << 1);
+    assert_eq!(leds.pixels[9], 40);
+}
@@ -0,0 +1,41 @@
+//! Smoke test running the whole game core against the board fakes.
+
+use light_rail::{
+    clock,
+    game::Game,
+    game_settings::GameSettings,
+    input::BoardInput,
+    modes::NUM_MODES,
+    random::Rand,
+    sim::{SimButtons, SimBuzzer, SimDigits, SimEeprom, SimLeds},
+};
+
+const TICKS_PER_MODE: u32 = 3000;
+
+// the game takes the board's platforms, which can only happen once per process
+#[test]
+fn every_mode_runs() {
+    Rand::seed(1);
+    let board_eeprom = SimEeprom::default();
+    let settings = GameSettings::new(&board_eeprom);
+    let mut game = Game::new(
+        SimBuzzer::default(),
+        SimDigits::default(),
+        BoardInput::new(SimButtons::default()),
+        SimLeds::default(),
+        board_eeprom,
+        settings,
+    );
+    game.restart();
+
+    for mode_index in 0..NUM_MODES {
+        game.state_mut().target_mode_index = mode_index;
+        for _ in 0..TICKS_PER_MODE {
+            clock::advance(clock::TICK_MS);
+            game.tick();
+        }
+
+        let lit = game.board_leds().pixels.iter().filter(|&&b| b > 0).count();
+        assert!(lit > 0, "mode {} left the board dark", mode_index);
+    }
+}
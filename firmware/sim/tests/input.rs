@@ -0,0 +1,231 @@
+//! Button gestures and chords.
+
+use light_rail::{
+    input::{
+        BoardInput, InputDirection, InputEvent, CHORD_LEFT_RIGHT, DEBOUNCE_CYCLES,
+        DOUBLE_TAP_CYCLES, HOLD_CYCLES, PRESS_CYCLES, REPEAT_CYCLES,
+    },
+    sim::SimButtons,
+};
+
+const UP: usize = 8;
+const DOWN: usize = 9;
+const LEFT: usize = 10;
+const RIGHT: usize = 11;
+
+fn direction_name(direction: InputDirection) -> &'static str {
+    match direction {
+        InputDirection::Up => "up",
+        InputDirection::Down => "down",
+        InputDirection::Left => "left",
+        InputDirection::Right => "right",
+    }
+}
+
+fn event_name(event: &InputEvent) -> String {
+    match *event {
+        InputEvent::SwitchButtonPressed(index) => format!("press {}", index),
+        InputEvent::SwitchButtonDoubleTapped(index) => format!("double {}", index),
+        InputEvent::SwitchButtonHeld(index) => format!("hold {}", index),
+        InputEvent::SwitchButtonRepeated(index) => format!("repeat {}", index),
+        InputEvent::SwitchButtonReleased(index) => format!("release {}", index),
+        InputEvent::SwitchButtonHeldReleased(index) => format!("held release {}", index),
+        InputEvent::DirectionButtonPressed(dir) => format!("press {}", direction_name(dir)),
+        InputEvent::DirectionButtonDoubleTapped(dir) => format!("double {}", direction_name(dir)),
+        InputEvent::DirectionButtonHeld(dir) => format!("hold {}", direction_name(dir)),
+        InputEvent::DirectionButtonRepeated(dir) => format!("repeat {}", direction_name(dir)),
+        InputEvent::DirectionButtonReleased(dir) => format!("release {}", direction_name(dir)),
+        InputEvent::DirectionButtonHeldReleased(dir) => {
+            format!("held release {}", direction_name(dir))
+        }
+        InputEvent::ChordPressed(chord) => format!("chord {:#x}", chord),
+    }
+}
+
+/// Scans the buttons once per step, each step lists the buttons down during it.
+/// Returns the events with the step they were sent on.
+fn run(steps: &[&[usize]]) -> Vec<(usize, String)> {
+    let mut input = BoardInput::new(SimButtons::default());
+    let mut events = Vec::new();
+    for (step, buttons) in steps.iter().enumerate() {
+        input.buttons_mut().pressed = [false; 12];
+        for &button in buttons.iter() {
+            input.buttons_mut().pressed[button] = true;
+        }
+        for event in input.update() {
+            events.push((step, event_name(&event)));
+        }
+    }
+    events
+}
+
+fn names(events: &[(usize, String)]) -> Vec<&str> {
+    events.iter().map(|(_, name)| name.as_str()).collect()
+}
+
+#[test]
+fn buttons_down_in_the_same_scan_only_chord() {
+    let both: &[usize] = &[LEFT, RIGHT];
+    let mut steps = vec![both; 80];
+    steps.push(&[]);
+    let events = run(&steps);
+    let chord = format!("chord {:#x}", CHORD_LEFT_RIGHT);
+    assert_eq!(names(&events), [chord.as_str()]);
+}
+
+#[test]
+fn press_is_held_back_for_a_chord() {
+    let mut steps: Vec<&[usize]> = vec![&[LEFT]];
+    steps.extend(vec![&[LEFT, RIGHT][..]; PRESS_CYCLES as usize + 5]);
+    steps.push(&[]);
+    let events = run(&steps);
+    let chord = format!("chord {:#x}", CHORD_LEFT_RIGHT);
+    assert_eq!(names(&events), [chord.as_str()]);
+}
+
+#[test]
+fn late_button_joins_a_held_button() {
+    let mut steps: Vec<&[usize]> = vec![&[UP]; 10];
+    steps.extend(vec![&[UP, DOWN][..]; 60]);
+    steps.push(&[UP]);
+    steps.push(&[]);
+    let events = run(&steps);
+    // the held button already pressed, after the chord neither button sends events of its own
+    assert_eq!(names(&events), ["press up", "chord 0x300"]);
+}
+
+#[test]
+fn buttons_send_events_again_after_a_chord() {
+    let mut steps: Vec<&[usize]> = vec![&[LEFT, RIGHT]; 5];
+    steps.extend(vec![&[][..]; 5]);
+    steps.extend(vec![&[LEFT][..]; 5]);
+    steps.push(&[]);
+    let events = run(&steps);
+    let chord = format!("chord {:#x}", CHORD_LEFT_RIGHT);
+    assert_eq!(
+        names(&events),
+        [chord.as_str(), "press left", "release left"]
+    );
+}
+
+#[test]
+fn press_and_release() {
+    let mut steps: Vec<&[usize]> = vec![&[LEFT]; 10];
+    steps.push(&[]);
+    let events = run(&steps);
+    assert_eq!(names(&events), ["press left", "release left"]);
+    assert_eq!(events[0].0, PRESS_CYCLES as usize - 1);
+}
+
+#[test]
+fn short_tap_still_presses() {
+    let events = run(&[&[2], &[]]);
+    assert_eq!(names(&events), ["press 2", "release 2"]);
+}
+
+#[test]
+fn double_tap() {
+    let mut steps: Vec<&[usize]> = vec![&[RIGHT]; 5];
+    steps.extend(vec![&[][..]; 10]);
+    steps.extend(vec![&[RIGHT][..]; 5]);
+    steps.push(&[]);
+    let events = run(&steps);
+    assert_eq!(
+        names(&events),
+        [
+            "press right",
+            "release right",
+            "press right",
+            "double right",
+            "release right"
+        ]
+    );
+}
+
+#[test]
+fn slow_taps_are_not_a_double_tap() {
+    let mut steps: Vec<&[usize]> = vec![&[RIGHT]; 5];
+    steps.extend(vec![&[][..]; DOUBLE_TAP_CYCLES as usize + 5]);
+    steps.extend(vec![&[RIGHT][..]; 5]);
+    steps.push(&[]);
+    let events = run(&steps);
+    assert_eq!(
+        names(&events),
+        [
+            "press right",
+            "release right",
+            "press right",
+            "release right"
+        ]
+    );
+}
+
+#[test]
+fn hold_repeats_until_released() {
+    let repeats = 3;
+    let held = HOLD_CYCLES as usize + repeats * REPEAT_CYCLES as usize;
+    let mut steps: Vec<&[usize]> = vec![&[DOWN]; held];
+    steps.push(&[]);
+    let events = run(&steps);
+    assert_eq!(
+        names(&events),
+        [
+            "press down",
+            "hold down",
+            "repeat down",
+            "repeat down",
+            "repeat down",
+            "held release down"
+        ]
+    );
+    assert_eq!(events[1].0, HOLD_CYCLES as usize - 1);
+}
+
+#[test]
+fn presses_are_ignored_while_debouncing() {
+    let mut steps: Vec<&[usize]> = vec![&[0]; 5];
+    steps.push(&[]);
+    // contact bounce right after the release
+    steps.extend(vec![&[0][..]; DEBOUNCE_CYCLES as usize]);
+    steps.push(&[]);
+    let events = run(&steps);
+    assert_eq!(names(&events), ["press 0", "release 0"]);
+}
+
+#[test]
+fn switch_tap_during_a_held_direction_keeps_both() {
+    let mut steps: Vec<&[usize]> = vec![&[RIGHT]; HOLD_CYCLES as usize];
+    steps.extend(vec![&[RIGHT, 4][..]; 2]);
+    steps.extend(vec![&[RIGHT][..]; REPEAT_CYCLES as usize]);
+    steps.push(&[]);
+    let events = run(&steps);
+    assert_eq!(
+        names(&events),
+        [
+            "press right",
+            "hold right",
+            "press 4",
+            "release 4",
+            "repeat right",
+            "held release right"
+        ]
+    );
+}
+
+#[test]
+fn switches_tapped_together_both_press() {
+    let events = run(&[&[1, 6], &[1, 6], &[]]);
+    assert_eq!(
+        names(&events),
+        ["press 1", "press 6", "release 1", "release 6"]
+    );
+}
+
+#[test]
+fn switch_presses_on_the_first_scan() {
+    let mut steps: Vec<&[usize]> = vec![&[3]; 10];
+    steps.push(&[]);
+    let events = run(&steps);
+    assert_eq!(names(&events), ["press 3", "release 3"]);
+    assert_eq!(events[0].0, 0);
+}
@@ -0,0 +1,85 @@
+//! Cargo blink patterns, drawn in software or by the LED driver.
+
+use light_rail::{cargo::LedPattern, game_settings::GameSettings};
+
+const MIN_B: u8 = 50;
+const MAX_B: u8 = 100;
+const BLINK_PERIOD_TICKS: u32 = 27; // 0.27s in game ticks
+
+/// Counts the on to off edges in one cycle of the pattern, the flashes a player sees
+fn flashes(pattern: LedPattern, min_b: u8, max_b: u8) -> usize {
+    let brightness: Vec<u8> = (0..=255)
+        .map(|phase| pattern.get_pwm(phase, min_b, max_b))
+        .collect();
+    (0..128)
+        .filter(|&phase| brightness[phase] > brightness[phase + 1])
+        .count()
+}
+
+#[test]
+fn blink_patterns_flash_their_count_once_a_cycle() {
+    for (pattern, count) in [
+        (LedPattern::Blink1, 1),
+        (LedPattern::Blink2, 2),
+        (LedPattern::Blink3, 3),
+    ] {
+        assert_eq!(flashes(pattern, MIN_B, MAX_B), count);
+        assert_eq!(flashes(pattern, MIN_B, 0), count);
+    }
+}
+
+#[test]
+fn driver_blinks_only_patterns_it_reproduces() {
+    assert_eq!(LedPattern::Blink1.hardware_blink(MIN_B, MAX_B), Some(MAX_B));
+    assert_eq!(LedPattern::Blink1.hardware_blink(MIN_B, 0), Some(MIN_B));
+    for pattern in [
+        LedPattern::Solid,
+        LedPattern::Blink2,
+        LedPattern::Blink3,
+        LedPattern::Fade,
+    ] {
+        assert!(pattern.hardware_blink(MIN_B, MAX_B).is_none());
+    }
+
+    // the driver blinks evenly between the brightness and off
+    for (min_b, max_b) in [(MIN_B, MAX_B), (MIN_B, 0)] {
+        let on = LedPattern::Blink1.hardware_blink(min_b, max_b).unwrap();
+        let brightness: Vec<u8> = (0..128)
+            .map(|phase| LedPattern::Blink1.get_pwm(phase, min_b, max_b))
+            .collect();
+        assert_eq!(brightness.iter().filter(|&&b| b == on).count(), 64);
+        assert_eq!(brightness.iter().filter(|&&b| b == 0).count(), 64);
+    }
+}
+
+#[test]
+fn driver_blink_period_matches_the_pattern_cycle() {
+    let mut settings = GameSettings::default();
+    while settings.game_speed_level() > 0 {
+        settings.dec_game_speed_level();
+    }
+    loop {
+        // ticks for a pattern cycle of 128 phase steps
+        let mut phase = 0u32;
+        let mut ticks = 0;
+        while phase < 128 << 8 {
+            phase += settings.advance_phase(0, 1) as u32;
+            ticks += 1;
+        }
+
+        let period_ticks = settings
+            .blink_period()
+            .map(|period| period as u32 * BLINK_PERIOD_TICKS);
+        match period_ticks {
+            Some(period_ticks) => assert!(period_ticks.abs_diff(ticks) * 10 <= ticks),
+            // too slow for the driver, which blinks at most every 7 periods
+            None => assert!(ticks > 7 * BLINK_PERIOD_TICKS),
+        }
+
+        let level = settings.game_speed_level();
+        settings.inc_game_speed_level();
+        if settings.game_speed_level() == level {
+            break;
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Wear-leveled EEPROM record ring.
+
+use light_rail::{
+    record_ring::{RecordError, RecordRing},
+    sim::SimEeprom,
+};
+
+const CAPACITY: usize = 4;
+const SLOTS: u8 = 3;
+const SLOT_SIZE: usize = CAPACITY + 3;
+const RING: RecordRing<CAPACITY> = RecordRing::new(0x10, SLOTS);
+
+fn slot_bytes(eeprom: &SimEeprom, slot: usize) -> &[u8] {
+    let offset = 0x10 + slot * SLOT_SIZE;
+    &eeprom.bytes[offset..offset + SLOT_SIZE]
+}
+
+#[test]
+fn erased_ring_is_missing() {
+    let eeprom = SimEeprom::default();
+    assert!(matches!(RING.load(&eeprom), Err(RecordError::Missing)));
+}
+
+#[test]
+fn loads_the_newest_save() {
+    let mut eeprom = SimEeprom::default();
+    for value in 0..10 {
+        RING.save(&mut eeprom, &[value, 1, 2]);
+        assert_eq!(RING.load(&eeprom).ok().unwrap().as_slice(), &[value, 1, 2]);
+    }
+}
+
+#[test]
+fn saves_rotate_across_slots() {
+    let mut eeprom = SimEeprom::default();
+    RING.save(&mut eeprom, &[1]);
+    RING.save(&mut eeprom, &[2]);
+    assert_eq!(slot_bytes(&eeprom, 0)[2], 1);
+    assert_eq!(slot_bytes(&eeprom, 1)[2], 2);
+    assert!(slot_bytes(&eeprom, 2).iter().all(|&b| b == 0xFF));
+}
+
+#[test]
+fn unchanged_save_writes_nothing() {
+    let mut eeprom = SimEeprom::default();
+    RING.save(&mut eeprom, &[1, 2]);
+    let before = eeprom.bytes;
+    RING.save(&mut eeprom, &[1, 2]);
+    assert_eq!(eeprom.bytes, before);
+}
+
+#[test]
+fn corrupt_newest_slot_falls_back() {
+    let mut eeprom = SimEeprom::default();
+    RING.save(&mut eeprom, &[1]);
+    RING.save(&mut eeprom, &[2]);
+    // an interrupted save leaves the data without its checksum
+    eeprom.bytes[0x10 + SLOT_SIZE + 2] = 3;
+    assert_eq!(RING.load(&eeprom).ok().unwrap().as_slice(), &[1]);
+}
+
+#[test]
+fn all_slots_corrupt_is_corrupt() {
+    let mut eeprom = SimEeprom::default();
+    RING.save(&mut eeprom, &[1]);
+    eeprom.bytes[0x10 + 2] = 3;
+    assert!(matches!(RING.load(&eeprom), Err(RecordError::Corrupt)));
+}
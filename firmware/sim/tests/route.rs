@@ -0,0 +1,81 @@
+//! Shortest routes over the track graph.
+
+use light_rail::{
+    location::{Direction, Location},
+    route::Route,
+};
+
+const WALK_LEN: usize = 40;
+
+/// Track locations next to the platforms, heading both ways
+fn starts() -> Vec<(Location, Direction)> {
+    Location::platform_locs()
+        .iter()
+        .map(|platform| platform.next_loc(Direction::Anode, false))
+        .flat_map(|loc| [(loc, Direction::Anode), (loc, Direction::Cathode)])
+        .collect()
+}
+
+fn switch_index(loc: Location) -> Option<usize> {
+    Location::switch_locs()
+        .iter()
+        .position(|&switch| switch == loc)
+}
+
+/// Follows the route from the start, throwing the switches it requires, returns where it ends
+fn follow(route: &Route, start: Location, direction: Direction) -> Location {
+    let (mut loc, mut dir) = (start, direction);
+    for _ in 0..route.distance() {
+        let switched = switch_index(loc)
+            .and_then(|index| route.required_state(index, dir))
+            .unwrap_or(false);
+        (loc, dir) = loc.next(dir, switched);
+    }
+    loc
+}
+
+#[test]
+fn start_is_its_own_route() {
+    for (start, direction) in starts() {
+        let route = Route::find(start, direction, start, 0).unwrap();
+        assert_eq!(route.distance(), 0);
+    }
+}
+
+#[test]
+fn routes_lead_to_their_targets_no_longer_than_any_walk() {
+    // walks taking every fork, no fork, and alternating forks
+    let patterns: [fn(usize) -> bool; 3] = [|_| true, |_| false, |step| step % 2 == 0];
+    for (start, direction) in starts() {
+        for pattern in patterns {
+            let (mut loc, mut dir) = (start, direction);
+            for step in 1..=WALK_LEN {
+                (loc, dir) = loc.next(dir, pattern(step));
+                let route = Route::find(start, direction, loc, u8::MAX).unwrap();
+                assert!(route.distance() as usize <= step);
+                assert!(route.end() == loc);
+                assert!(follow(&route, start, direction) == loc);
+            }
+        }
+    }
+}
+
+#[test]
+fn targets_beyond_max_distance_are_not_found() {
+    for (start, direction) in starts() {
+        let target = start.next_loc(direction, false);
+        if target == start {
+            continue;
+        }
+        assert!(Route::find(start, direction, target, 0).is_none());
+        assert!(Route::find(start, direction, target, 1).is_some());
+    }
+}
+
+#[test]
+fn blocked_track_has_no_clear_route() {
+    for (start, direction) in starts() {
+        assert!(Route::find_clear(start, direction, 4, |loc| loc != start).is_none());
+        assert!(Route::find_clear(start, direction, 4, |_| false).is_some());
+    }
+}
@@ -0,0 +1,259 @@
+//! Car pool shared by the trains.
+
+use light_rail::{
+    cargo::{Cargo, LedPattern},
+    game_settings::GameSettings,
+    game_state::MAX_CARS,
+    location::{Direction, Location},
+    switch::Switch,
+    train::{CollisionKind, TrainMut, TrainStyle, Trains},
+};
+
+const SOLID: Cargo = Cargo::Full(LedPattern::Solid);
+
+/// Returns the track location next to a platform, where trains start
+fn track_loc(platform_index: usize) -> Location {
+    Location::platform_locs()[platform_index].next_loc(Direction::Anode, false)
+}
+
+fn car_locs(trains: &Trains, index: usize) -> Vec<u8> {
+    let train = trains.get(index).unwrap();
+    train.cars().iter().map(|car| car.loc.index()).collect()
+}
+
+/// Two trains far apart on the board, the first with num_cars empty cars and the second with 3 loaded ones
+fn two_trains(num_cars: u8) -> Trains {
+    let mut trains = Trains::default();
+    trains.push(track_loc(0), Cargo::Empty, 16, None).unwrap();
+    trains.push(track_loc(20), SOLID, 16, None).unwrap();
+    for _ in 1..num_cars {
+        trains.get_mut(0).unwrap().add_car(Cargo::Empty).unwrap();
+    }
+    for _ in 1..3 {
+        trains.get_mut(1).unwrap().add_car(SOLID).unwrap();
+    }
+    trains
+}
+
+#[test]
+fn growing_a_train_shifts_the_following_trains() {
+    let mut trains = two_trains(2);
+    let second = car_locs(&trains, 1);
+
+    trains.get_mut(0).unwrap().add_car(Cargo::Empty).unwrap();
+    assert_eq!(trains.num_cars(), 6);
+    assert_eq!(trains.get(0).unwrap().len(), 3);
+    assert_eq!(car_locs(&trains, 1), second);
+    assert!(trains
+        .get(1)
+        .unwrap()
+        .cars()
+        .iter()
+        .all(|car| car.cargo == SOLID));
+
+    trains.get_mut(0).unwrap().remove_car().unwrap();
+    trains.get_mut(0).unwrap().remove_car().unwrap();
+    assert_eq!(trains.num_cars(), 4);
+    assert_eq!(car_locs(&trains, 1), second);
+}
+
+#[test]
+fn engine_is_never_removed() {
+    let mut trains = two_trains(1);
+    assert!(trains.get_mut(0).unwrap().remove_car().is_none());
+    assert_eq!(trains.get(0).unwrap().len(), 1);
+}
+
+#[test]
+fn removing_a_train_compacts_the_pool() {
+    let mut trains = two_trains(4);
+    let second = car_locs(&trains, 1);
+
+    trains.remove(0);
+    assert_eq!(trains.len(), 1);
+    assert_eq!(trains.num_cars(), 3);
+    assert_eq!(car_locs(&trains, 0), second);
+
+    // the remaining train owns its locations under its new index
+    assert_eq!(trains.train_at(track_loc(20)), Some(0));
+    assert!(!trains.at_location(track_loc(0)));
+}
+
+#[test]
+fn cars_are_tracked_in_the_occupancy_map() {
+    let mut trains = two_trains(3);
+    for index in 0..trains.len() {
+        let train = trains.get(index).unwrap();
+        assert!(train
+            .cars()
+            .iter()
+            .all(|car| trains.train_at(car.loc) == Some(index)));
+    }
+
+    let caboose = trains.get_mut(0).unwrap().remove_car().unwrap();
+    assert!(!trains.at_location(caboose));
+}
+
+#[test]
+fn trains_are_capped_at_max_cars() {
+    let mut trains = Trains::default();
+    trains.push(track_loc(0), Cargo::Empty, 2, None).unwrap();
+    assert!(trains.get_mut(0).unwrap().add_car(Cargo::Empty).is_some());
+    assert!(trains.get_mut(0).unwrap().add_car(Cargo::Empty).is_none());
+    assert!(trains.num_cars() < MAX_CARS);
+}
+
+/// Places a train of num_cars with its engine at loc, heading in direction
+fn place_train(trains: &mut Trains, loc: Location, direction: Direction, num_cars: u8) -> usize {
+    let index = trains.push(loc, Cargo::Empty, 16, Some(100)).unwrap();
+    let mut train = trains.get_mut(index).unwrap();
+    if train.direction() != direction {
+        train.reverse();
+    }
+    for _ in 1..num_cars {
+        train.add_car(Cargo::Empty).unwrap();
+    }
+    index
+}
+
+/// Moves every train one location, at full speed every advance is a move
+fn move_all(trains: &mut Trains) {
+    let settings = GameSettings::default();
+    for index in 0..trains.len() {
+        let mut train = trains.get_mut(index).unwrap();
+        assert!(train.advance(&settings, &[], TrainStyle::Classic, |_, _| {}, false));
+    }
+}
+
+/// Two trains of num_cars with their engines next to each other, heading toward each other
+fn facing_trains(num_cars: u8) -> Trains {
+    let loc = track_loc(10);
+    let (next, arrival) = loc.next(Direction::Anode, false);
+    let mut trains = Trains::default();
+    place_train(&mut trains, loc, Direction::Anode, num_cars);
+    place_train(&mut trains, next, arrival.opposite(), num_cars);
+    trains
+}
+
+#[test]
+fn engines_passing_through_each_other_collide_head_on() {
+    let mut trains = facing_trains(1);
+    move_all(&mut trains);
+
+    let collision = trains.collision(0).unwrap();
+    assert!(collision.kind == CollisionKind::HeadOn);
+    assert!(collision.involves(0, 1));
+    assert!(trains.collision(1).unwrap().kind == CollisionKind::HeadOn);
+}
+
+#[test]
+fn long_trains_meeting_collide_head_on() {
+    let mut trains = facing_trains(3);
+    move_all(&mut trains);
+    assert!(trains.collision(0).unwrap().kind == CollisionKind::HeadOn);
+}
+
+#[test]
+fn trains_following_each_other_dont_collide() {
+    let loc = track_loc(10);
+    let (next, arrival) = loc.next(Direction::Anode, false);
+    let mut trains = Trains::default();
+    place_train(&mut trains, loc, Direction::Anode, 1);
+    place_train(&mut trains, next, arrival, 1);
+    move_all(&mut trains);
+    assert!(trains.collision(0).is_none());
+    assert!(trains.collision(1).is_none());
+}
+
+#[test]
+fn speed_set_while_dwelling_is_kept_until_departure() {
+    let settings = GameSettings::default();
+    let mut trains = Trains::default();
+    place_train(&mut trains, track_loc(10), Direction::Anode, 1);
+    let mut train = trains.get_mut(0).unwrap();
+    train.stop_at_station(20);
+    train.set_target_speed(50);
+    assert_eq!(train.target_speed(), 50);
+
+    for _ in 1..20 {
+        assert!(!train.advance(&settings, &[], TrainStyle::Classic, |_, _| {}, false));
+        assert!(train.is_dwelling());
+        assert_eq!(train.speed(), 0);
+    }
+
+    train.advance(&settings, &[], TrainStyle::Classic, |_, _| {}, false);
+    assert!(!train.is_dwelling());
+    assert_eq!(train.target_speed(), 50);
+}
+
+/// Advances the train until it moves, returns where the engine moved to and the LED updates on the way
+fn move_engine(
+    train: &mut TrainMut,
+    settings: &GameSettings,
+    switches: &[Switch],
+) -> (u8, Vec<(u8, u8)>) {
+    let mut updates = Vec::new();
+    loop {
+        let moved = train.advance(
+            settings,
+            switches,
+            TrainStyle::Classic,
+            |loc, brightness| updates.push((loc.index(), brightness)),
+            false,
+        );
+        if moved {
+            return (train.front().index(), updates);
+        }
+    }
+}
+
+#[test]
+fn lone_engine_reversed_past_a_switch_heads_back_over_its_fork() {
+    let mut switches = Switch::take();
+    // a switch forked one way only, entered from its fork
+    let (index, fork_dir) = (0..switches.len())
+        .flat_map(|index| [(index, Direction::Anode), (index, Direction::Cathode)])
+        .find(|&(index, direction)| {
+            switches[index].fork_location(direction).is_some()
+                && switches[index]
+                    .fork_location(direction.opposite())
+                    .is_none()
+        })
+        .unwrap();
+    switches[index].set_switched(fork_dir, true);
+    let switches = &switches[index..=index];
+    let switch_loc = switches[0].location();
+    let fork_loc = switches[0].fork_location(fork_dir).unwrap();
+
+    let mut settings = GameSettings::default();
+    if !settings.is_smooth_motion() {
+        settings.toggle_smooth_motion();
+    }
+    let mut trains = Trains::default();
+    trains.push(fork_loc, Cargo::Empty, 16, Some(50)).unwrap();
+    let mut train = trains.get_mut(0).unwrap();
+    if fork_loc.direction_to(switch_loc) != Some(train.direction()) {
+        train.reverse();
+    }
+    let (engine_loc, _) = move_engine(&mut train, &settings, switches);
+    assert_eq!(engine_loc, switch_loc.index());
+    move_engine(&mut train, &settings, switches);
+    let past_loc = train.front();
+
+    // half way to the next location, the LED ahead is lit
+    train.advance(&settings, switches, TrainStyle::Classic, |_, _| {}, false);
+    let (lead_loc, _) = train.lead().unwrap();
+
+    train.reverse();
+    assert!(past_loc.direction_to(switch_loc) == Some(train.direction()));
+    assert!(train.last_loc() == lead_loc);
+    assert!(train.lead().is_none());
+    assert!(train.trail().iter().all(|&(_, brightness)| brightness == 0));
+
+    let (engine_loc, updates) = move_engine(&mut train, &settings, switches);
+    assert_eq!(engine_loc, switch_loc.index());
+    // the LED lit ahead before turning around goes dark
+    assert!(updates.contains(&(lead_loc.index(), 0)));
+    let (engine_loc, _) = move_engine(&mut train, &settings, switches);
+    assert_eq!(engine_loc, fork_loc.index());
+}